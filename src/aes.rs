@@ -5,10 +5,12 @@ use std::{
     fmt,
     marker::Unpin,
     io,
+    convert::TryFrom,
 };
+pub use openssl::symm::Cipher;
 use openssl::{
     symm::{
-	Cipher,
+	self,
 	Crypter,
 	Mode,
     },
@@ -17,20 +19,43 @@ use openssl::{
 use tokio::io::{
     AsyncRead,
     AsyncReadExt,
+    AsyncBufRead,
+    AsyncBufReadExt,
+    AsyncSeek,
     AsyncWrite,
     AsyncWriteExt,
+    ReadBuf,
+};
+#[cfg(feature="async")]
+use std::{
+    pin::Pin,
+    task::{
+	Context,
+	Poll,
+    },
 };
 use getrandom::getrandom;
+use rand_core::RngCore;
+use zeroize::Zeroizing;
 
 const KEYSIZE: usize = consts::AES_KEYSIZE;
 const IVSIZE: usize = consts::AES_IVSIZE;
 use consts::BUFFER_SIZE;
 const BLOCKSIZE: usize = 16;
 
+/// Size of the random nonce at the start of a `seal()`ed blob
+pub const SEAL_NONCESIZE: usize = 12;
+/// Size of the authentication tag at the end of a `seal()`ed blob
+pub const SEAL_TAGSIZE: usize = 16;
+
 /// A key and IV for the AES algorithm
-#[derive(Debug, PartialEq, Eq, Clone, Hash, Default, PartialOrd, Ord)]
+///
+/// # Binary layout
+/// The binary representation (`into_bytes()`, `from_bytes()`, `AsRef<[u8]>`) is `KEYSIZE + IVSIZE` bytes: the key, then the IV.
+/// This is a stable format, safe to store and exchange between systems.
+#[derive(PartialEq, Eq, Clone, Hash, Default, PartialOrd, Ord)]
 #[cfg_attr(feature="serialise", derive(Serialize,Deserialize))]
-#[repr(align(1))]
+#[repr(C, align(1))]
 pub struct AesKey {
     key: [u8; KEYSIZE],
     iv: [u8; IVSIZE],
@@ -48,11 +73,54 @@ impl AesKey
 
 	Ok(this)
     }
+
+    /// Generate a new AES key and IV from a specific RNG.
+    ///
+    /// # Notes
+    /// `generate()` uses the OS's RNG, this is for when the output must be reproducible (e.g. tests with a seeded RNG). Only use a cryptographically secure RNG for real keys.
+    pub fn generate_with_rng(mut rng: impl RngCore) -> Result<Self, Error>
+    {
+	let mut this = Self::default();
+
+	rng.try_fill_bytes(&mut this.key[..]).map_err(|_| Error::Random)?;
+	rng.try_fill_bytes(&mut this.iv[..]).map_err(|_| Error::Random)?;
+
+	Ok(this)
+    }
+
+    /// Derive an AES key and IV from a passphrase and salt.
+    ///
+    /// Both are derived together with `PASSWORD_ROUNDS` rounds of PBKDF2-HMAC-SHA256.
+    /// The salt is prefixed with a fixed label first, so the key is not the same as the `Password` hash of the same passphrase and salt.
+    #[cfg(feature="password")]
+    pub fn derive_from_password(password: impl AsRef<str>, salt: &password::Salt) -> Self
+    {
+	const LABEL: &[u8] = b"cryptohelpers aes-key";
+
+	let mut bytes = [0u8; KEYSIZE+IVSIZE];
+	password::derive_key_labelled(LABEL, password, salt, consts::PASSWORD_ROUNDS, &mut bytes[..]);
+	Self::from_bytes(bytes)
+    }
+
+    /// Overwrite the key and IV of this instance with new random bytes.
+    ///
+    /// # Notes
+    /// The old key and IV are zeroed first, so they are not left behind if generating the new ones fails.
+    pub fn regenerate(&mut self) -> Result<(), Error>
+    {
+	*self = Self::empty();
+
+	getrandom(&mut self.key[..])?;
+	getrandom(&mut self.iv[..])?;
+
+	Ok(())
+    }
+
     /// Generate a new random AES key and IV.
     ///
     /// # Deprecated
     /// Use `AesKey::generate()` instead.
-    #[deprecated] #[inline]  pub fn random() -> Result<Self, Error>
+    #[deprecated(note = "use `AesKey::generate()`, which is the same")] #[inline]  pub fn random() -> Result<Self, Error>
     {
 	Self::generate()
     }
@@ -69,16 +137,28 @@ impl AesKey
         (self.key, self.iv)
     }
 
-    /// Consume this instance into the full byte buffer
+    /// Consume this instance into the full byte buffer, the key followed by the IV
     pub fn into_bytes(self) -> [u8; KEYSIZE+IVSIZE]
     {
-        unsafe { std::mem::transmute(self) }
+	let mut output = [0u8; KEYSIZE+IVSIZE];
+	bytes::copy_slice(&mut output[..KEYSIZE], &self.key[..]);
+	bytes::copy_slice(&mut output[KEYSIZE..], &self.iv[..]);
+	output
+    }
+
+    /// Consume this instance into the full byte buffer, which is zeroed when dropped
+    #[inline] pub fn into_bytes_zeroizing(self) -> Zeroizing<[u8; KEYSIZE+IVSIZE]>
+    {
+	Zeroizing::new(self.into_bytes())
     }
 
-    /// Consume a full byte buffer into an AES key
+    /// Consume a full byte buffer, the key followed by the IV, into an AES key
     pub fn from_bytes(from: [u8; KEYSIZE+IVSIZE]) -> Self
-    {	
-        unsafe { std::mem::transmute(from) }
+    {
+	let mut this = Self::empty();
+	bytes::copy_slice(&mut this.key[..], &from[..KEYSIZE]);
+	bytes::copy_slice(&mut this.iv[..], &from[KEYSIZE..]);
+	this
     }
 
     /// Create a zero inisialised key
@@ -90,15 +170,16 @@ impl AesKey
     /// Create a new instance from slices
     pub fn from_slice(key: impl AsRef<[u8]>, iv: impl AsRef<[u8]>) -> Result<Self,Error>
     {
+	let (key, iv) = (key.as_ref(), iv.as_ref());
 	let mut this = Self::default();
-	if bytes::copy_slice(&mut this.key[..], key.as_ref()) != this.key.len() {
-	    Err(Error::Length{expected: Some(this.key.len()), got: None})
+	if bytes::copy_slice(&mut this.key[..], key) != this.key.len() {
+	    Err(Error::Length{expected: Some(this.key.len()), got: Some(key.len())})
 	} else {
 	    Ok(())
 	}?;
 
-	if bytes::copy_slice(&mut this.iv[..], iv.as_ref()) != this.iv.len() {
-	    Err(Error::Length{expected: Some(this.iv.len()), got: None})
+	if bytes::copy_slice(&mut this.iv[..], iv) != this.iv.len() {
+	    Err(Error::Length{expected: Some(this.iv.len()), got: Some(iv.len())})
 	} else {
 	    Ok(this)
 	}
@@ -145,179 +226,1704 @@ impl AsMut<[u8]> for AesKey
     }
 }
 
+/// Does not print the key or IV, use `Display` for that.
+impl fmt::Debug for AesKey
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+	f.debug_struct("AesKey").finish_non_exhaustive()
+    }
+}
+
 impl fmt::Display for AesKey
 {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
     {
 	write!(f, "AesKey (Key: ")?;
-	for byte in self.key.iter() {
-	    write!(f, "{:0x}", byte)?;
-	}
+	bytes::fmt_hex(&self.key[..], f, false)?;
 	write!(f, ", IV: ")?;
-	for byte in self.iv.iter() {
-	    write!(f, "{:0x}", byte)?;
-	}
+	bytes::fmt_hex(&self.iv[..], f, false)?;
 	write!(f, ")")
     }
 }
 
-/// Encrypt a stream into another using a key
-#[cfg(feature="async")] 
-pub async fn encrypt_stream<F,T>(key: &AesKey, from: &mut F, to: &mut T) -> Result<usize, Error>
-where F: AsyncRead + Unpin + ?Sized,
-      T: AsyncWrite + Unpin + ?Sized
+/// Formats the binary representation (the key, then the IV) as hex
+impl fmt::LowerHex for AesKey
 {
-    let mut read;
-    let mut done=0;
+    #[inline] fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+	bytes::fmt_hex(self.as_ref(), f, false)
+    }
+}
 
-    let mut crypter = Crypter::new(Cipher::aes_128_cbc(), Mode::Encrypt, &key.key[..], Some(&key.iv[..]))?;
-    let mut buffer = [0u8; BUFFER_SIZE];
-    let mut crypt_buffer = [0u8; BUFFER_SIZE + BLOCKSIZE];
-    while {read = from.read(&mut buffer[..]).await?; read!=0} {
-	let bytes_encrypted = crypter.update(&buffer[..read], &mut crypt_buffer)?;
-	to.write_all(&crypt_buffer[..bytes_encrypted]).await?;
-	done += bytes_encrypted;
+/// Formats the binary representation (the key, then the IV) as hex
+impl fmt::UpperHex for AesKey
+{
+    #[inline] fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+	bytes::fmt_hex(self.as_ref(), f, true)
     }
+}
 
-    let bytes_encrypted = crypter.finalize(&mut crypt_buffer)?;
-    to.write_all(&crypt_buffer[..bytes_encrypted]).await?;
+impl TryFrom<&[u8]> for AesKey
+{
+    type Error = Error;
 
-    Ok(done + bytes_encrypted)
+    /// Fails if `from` is not exactly `KEYSIZE + IVSIZE` bytes long
+    fn try_from(from: &[u8]) -> Result<Self, Self::Error>
+    {
+	if from.len() != KEYSIZE + IVSIZE {
+	    return Err(Error::Length{expected: Some(KEYSIZE + IVSIZE), got: Some(from.len())});
+	}
+	Self::from_slice(&from[..KEYSIZE], &from[KEYSIZE..])
+    }
 }
 
-/// Encrypt a stream into another using a key
-pub fn encrypt_stream_sync<F,T>(key: &AesKey, from: &mut F, to: &mut T) -> Result<usize, Error>
-where F: io::Read + ?Sized,
-      T: io::Write + ?Sized
+/// The cipher an AES key is intended for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature="serialise", derive(Serialize,Deserialize))]
+#[non_exhaustive]
+pub enum AesAlgorithm
 {
-    let mut read;
-    let mut done=0;
+    /// AES-128 in CBC mode. This is the default, and what `AesKey` is always used with
+    Aes128Cbc,
+    /// AES-256 in CBC mode
+    Aes256Cbc,
+}
 
-    let mut crypter = Crypter::new(Cipher::aes_128_cbc(), Mode::Encrypt, &key.key[..], Some(&key.iv[..]))?;
-    let mut buffer = [0u8; BUFFER_SIZE];
-    let mut crypt_buffer = [0u8; BUFFER_SIZE + BLOCKSIZE];
-    while {read = from.read(&mut buffer[..])?; read!=0} {
-	let bytes_encrypted = crypter.update(&buffer[..read], &mut crypt_buffer)?;
-	to.write_all(&crypt_buffer[..bytes_encrypted])?;
-	done += bytes_encrypted;
+impl AesAlgorithm
+{
+    /// The openssl cipher for this algorithm
+    #[inline] pub fn cipher(self) -> Cipher
+    {
+	match self {
+	    Self::Aes128Cbc => Cipher::aes_128_cbc(),
+	    Self::Aes256Cbc => Cipher::aes_256_cbc(),
+	}
     }
 
-    let bytes_encrypted = crypter.finalize(&mut crypt_buffer)?;
-    to.write_all(&crypt_buffer[..bytes_encrypted])?;
+    /// Size of the key for this algorithm in bytes
+    #[inline] pub const fn key_size(self) -> usize
+    {
+	match self {
+	    Self::Aes128Cbc => 16,
+	    Self::Aes256Cbc => 32,
+	}
+    }
 
-    Ok(done + bytes_encrypted)
+    /// Size of the IV for this algorithm in bytes
+    #[inline] pub const fn iv_size(self) -> usize
+    {
+	IVSIZE
+    }
+
+    const fn tag(self) -> u8
+    {
+	match self {
+	    Self::Aes128Cbc => 0,
+	    Self::Aes256Cbc => 1,
+	}
+    }
+
+    const fn from_tag(tag: u8) -> Option<Self>
+    {
+	match tag {
+	    0 => Some(Self::Aes128Cbc),
+	    1 => Some(Self::Aes256Cbc),
+	    _ => None,
+	}
+    }
 }
 
-/// Decrypt a stream into another using a key
-#[cfg(feature="async")] 
-pub async fn decrypt_stream<F,T>(key: &AesKey, from: &mut F, to: &mut T) -> Result<usize, Error>
-where F: AsyncRead + Unpin + ?Sized,
-      T: AsyncWrite + Unpin + ?Sized
+impl Default for AesAlgorithm
 {
-    let mut read;
-    let mut done=0;
+    #[inline]
+    fn default() -> Self
+    {
+	Self::Aes128Cbc
+    }
+}
 
-    let mut crypter = Crypter::new(Cipher::aes_128_cbc(), Mode::Decrypt, &key.key[..], Some(&key.iv[..]))?;
-    let mut buffer = [0u8; BUFFER_SIZE];
-    let mut crypt_buffer = [0u8; BUFFER_SIZE + BLOCKSIZE];
-    while {read = from.read(&mut buffer[..]).await?; read!=0} {
-	let bytes_encrypted = crypter.update(&buffer[..read], &mut crypt_buffer)?;
-	to.write_all(&crypt_buffer[..bytes_encrypted]).await?;
-	done += bytes_encrypted;
+/// Size of the largest key any `AesAlgorithm` uses
+const MAX_KEYSIZE: usize = 32;
+
+/// A key and IV tagged with the `AesAlgorithm` they are for.
+///
+/// Unlike `AesKey`, the key size depends on the algorithm, and the stream functions for this type use the algorithm's cipher. A key can never be used with the wrong cipher.
+#[derive(PartialEq, Eq, Clone, Hash)]
+#[cfg_attr(feature="serialise", derive(Serialize,Deserialize))]
+pub struct TaggedAesKey
+{
+    algorithm: AesAlgorithm,
+    key: [u8; MAX_KEYSIZE],
+    iv: [u8; IVSIZE],
+}
+
+impl TaggedAesKey
+{
+    /// Generate a new random key and IV for `algorithm`
+    pub fn generate(algorithm: AesAlgorithm) -> Result<Self, Error>
+    {
+	let mut this = Self {
+	    algorithm,
+	    key: [0u8; MAX_KEYSIZE],
+	    iv: [0u8; IVSIZE],
+	};
+
+	getrandom(this.k_mut())?;
+	getrandom(&mut this.iv[..])?;
+
+	Ok(this)
     }
 
-    let bytes_encrypted = crypter.finalize(&mut crypt_buffer)?;
-    to.write_all(&crypt_buffer[..bytes_encrypted]).await?;
+    /// Create a new instance from slices.
+    ///
+    /// # Errors
+    /// `Error::Length` if `key` or `iv` are not the right size for `algorithm`.
+    pub fn from_slice(algorithm: AesAlgorithm, key: impl AsRef<[u8]>, iv: impl AsRef<[u8]>) -> Result<Self, Error>
+    {
+	let (key, iv) = (key.as_ref(), iv.as_ref());
+	check_cipher(algorithm.cipher(), key, Some(iv))?;
 
-    Ok(done + bytes_encrypted)
+	let mut this = Self {
+	    algorithm,
+	    key: [0u8; MAX_KEYSIZE],
+	    iv: [0u8; IVSIZE],
+	};
+	bytes::copy_slice(this.k_mut(), key);
+	bytes::copy_slice(&mut this.iv[..], iv);
+	Ok(this)
+    }
+
+    /// The algorithm this key is for
+    #[inline] pub fn algorithm(&self) -> AesAlgorithm
+    {
+	self.algorithm
+    }
+
+    /// The key part of this instance
+    #[inline] pub fn k(&self) -> &[u8]
+    {
+	&self.key[..self.algorithm.key_size()]
+    }
+
+    /// The IV part of this instance
+    #[inline] pub fn i(&self) -> &[u8]
+    {
+	&self.iv[..]
+    }
+
+    #[inline] fn k_mut(&mut self) -> &mut [u8]
+    {
+	&mut self.key[..self.algorithm.key_size()]
+    }
+
+    /// Encode as bytes.
+    ///
+    /// The format is one byte identifying the algorithm, then the key, then the IV.
+    pub fn to_bytes(&self) -> Vec<u8>
+    {
+	let mut output = Vec::with_capacity(1 + self.k().len() + IVSIZE);
+	output.push(self.algorithm.tag());
+	output.extend_from_slice(self.k());
+	output.extend_from_slice(&self.iv[..]);
+	output
+    }
+
+    /// Decode from bytes created by `to_bytes()`
+    pub fn from_bytes(bytes: impl AsRef<[u8]>) -> Result<Self, Error>
+    {
+	let bytes = bytes.as_ref();
+	let algorithm = match bytes.first() {
+	    Some(&tag) => AesAlgorithm::from_tag(tag).ok_or(Error::Unknown)?,
+	    None => return Err(Error::Length{expected: None, got: Some(0)}),
+	};
+	let expected = 1 + algorithm.key_size() + algorithm.iv_size();
+	if bytes.len() != expected {
+	    return Err(Error::Length{expected: Some(expected), got: Some(bytes.len())});
+	}
+	let (key, iv) = bytes[1..].split_at(algorithm.key_size());
+	Self::from_slice(algorithm, key, iv)
+    }
+}
+
+impl From<AesKey> for TaggedAesKey
+{
+    /// Tag an `AesKey` with the algorithm it is used with, `Aes128Cbc`
+    fn from(from: AesKey) -> Self
+    {
+	let mut key = [0u8; MAX_KEYSIZE];
+	bytes::copy_slice(&mut key[..], &from.key[..]);
+	Self {
+	    algorithm: AesAlgorithm::Aes128Cbc,
+	    key,
+	    iv: from.iv,
+	}
+    }
+}
+
+/// Does not print the key or IV
+impl fmt::Debug for TaggedAesKey
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+	f.debug_struct("TaggedAesKey")
+	    .field("algorithm", &self.algorithm)
+	    .finish_non_exhaustive()
+    }
+}
+
+/// Encrypt a stream into another using a tagged key and its algorithm. Returns the number of bytes written to `to`.
+///
+/// # Notes
+/// `to` is flushed before returning.
+#[cfg(feature="async")] 
+#[inline] pub async fn encrypt_stream_tagged<F,T>(key: &TaggedAesKey, from: &mut F, to: &mut T) -> Result<usize, Error>
+where F: AsyncRead + Unpin + ?Sized,
+      T: AsyncWrite + Unpin + ?Sized
+{
+    crypt_stream_with(Mode::Encrypt, key.algorithm.cipher(), key.k(), Some(key.i()), from, to).await
 }
 
-/// Decrypt a stream into another using a key
-pub fn decrypt_stream_sync<F,T>(key: &AesKey, from: &mut F, to: &mut T) -> Result<usize, Error>
+/// Encrypt a stream into another using a tagged key and its algorithm. Returns the number of bytes written to `to`.
+///
+/// # Notes
+/// `to` is flushed before returning.
+#[inline] pub fn encrypt_stream_tagged_sync<F,T>(key: &TaggedAesKey, from: &mut F, to: &mut T) -> Result<usize, Error>
 where F: io::Read + ?Sized,
       T: io::Write + ?Sized
 {
-    let mut read;
-    let mut done=0;
+    crypt_stream_with_sync(Mode::Encrypt, key.algorithm.cipher(), key.k(), Some(key.i()), from, to)
+}
 
-    let mut crypter = Crypter::new(Cipher::aes_128_cbc(), Mode::Decrypt, &key.key[..], Some(&key.iv[..]))?;
-    let mut buffer = [0u8; BUFFER_SIZE];
-    let mut crypt_buffer = [0u8; BUFFER_SIZE + BLOCKSIZE];
-    while {read = from.read(&mut buffer[..])?; read!=0} {
-	let bytes_encrypted = crypter.update(&buffer[..read], &mut crypt_buffer)?;
-	to.write_all(&crypt_buffer[..bytes_encrypted])?;
-	done += bytes_encrypted;
-    }
+/// Decrypt a stream into another using a tagged key and its algorithm. Returns the number of bytes written to `to`.
+///
+/// # Notes
+/// `to` is flushed before returning.
+#[cfg(feature="async")] 
+#[inline] pub async fn decrypt_stream_tagged<F,T>(key: &TaggedAesKey, from: &mut F, to: &mut T) -> Result<usize, Error>
+where F: AsyncRead + Unpin + ?Sized,
+      T: AsyncWrite + Unpin + ?Sized
+{
+    crypt_stream_with(Mode::Decrypt, key.algorithm.cipher(), key.k(), Some(key.i()), from, to).await
+}
 
-    let bytes_encrypted = crypter.finalize(&mut crypt_buffer)?;
-    to.write_all(&crypt_buffer[..bytes_encrypted])?;
+/// Decrypt a stream into another using a tagged key and its algorithm. Returns the number of bytes written to `to`.
+///
+/// # Notes
+/// `to` is flushed before returning.
+#[inline] pub fn decrypt_stream_tagged_sync<F,T>(key: &TaggedAesKey, from: &mut F, to: &mut T) -> Result<usize, Error>
+where F: io::Read + ?Sized,
+      T: io::Write + ?Sized
+{
+    crypt_stream_with_sync(Mode::Decrypt, key.algorithm.cipher(), key.k(), Some(key.i()), from, to)
+}
 
-    Ok(done + bytes_encrypted)
+/// Encrypt a stream into another using a key. Returns the number of bytes written to `to`.
+///
+/// # Notes
+/// `to` is flushed before returning.
+#[cfg(feature="async")] 
+#[inline] pub async fn encrypt_stream<F,T>(key: &AesKey, from: &mut F, to: &mut T) -> Result<usize, Error>
+where F: AsyncRead + Unpin + ?Sized,
+      T: AsyncWrite + Unpin + ?Sized
+{
+    encrypt_stream_with_progress(key, from, to, |_| ()).await
 }
 
-/// Decrypt a slice to a `Vec<u8>` async
+/// Encrypt a stream into another using a key, calling `progress` with the total number of bytes read from `from` so far after each chunk. Returns the number of bytes written to `to`.
+///
+/// # Cancellation
+/// All of the encryption state is local to the future, so dropping it part way through leaves nothing behind.
+/// `to` is left holding whatever ciphertext had been written by then, which is incomplete and will not decrypt.
+///
+/// # Notes
+/// `to` is flushed before returning.
 #[cfg(feature="async")] 
-pub async fn decrypt_slice(key: &AesKey, from: impl AsRef<[u8]>) -> Result<Vec<u8>, Error>
+#[inline] pub async fn encrypt_stream_with_progress<F,T,P>(key: &AesKey, from: &mut F, to: &mut T, progress: P) -> Result<usize, Error>
+where F: AsyncRead + Unpin + ?Sized,
+      T: AsyncWrite + Unpin + ?Sized,
+      P: FnMut(usize)
 {
-    let from = from.as_ref();
-    let mut to = Vec::with_capacity(from.len() + 128);
-    
-    decrypt_stream(key, &mut &from[..], &mut to).await?;
-    Ok(to)
+    crypt_loop(Mode::Encrypt, Cipher::aes_128_cbc(), &key.key[..], Some(&key.iv[..]), from, to, progress).await
 }
 
-/// Decrypt a slice to a `Vec<u8>` sync
-pub fn decrypt_slice_sync(key: &AesKey, from: impl AsRef<[u8]>) -> Result<Vec<u8>, Error>
+/// Encrypt a stream into another using a key and IV held elsewhere, the same way `encrypt_stream()` does with an `AesKey`. Returns the number of bytes written to `to`.
+///
+/// # Notes
+/// `to` is flushed before returning.
+///
+/// # Errors
+/// `Error::Length` if `key` or `iv` are not the right size.
+#[cfg(feature="async")] 
+pub async fn encrypt_stream_raw<F,T>(key: &[u8], iv: &[u8], from: &mut F, to: &mut T) -> Result<usize, Error>
+where F: AsyncRead + Unpin + ?Sized,
+      T: AsyncWrite + Unpin + ?Sized
 {
-    let from = from.as_ref();
-    let mut to = Vec::with_capacity(from.len() + 128);
-    
-    decrypt_stream_sync(key, &mut &from[..], &mut to)?;
-    Ok(to)
+    crypt_stream_with(Mode::Encrypt, Cipher::aes_128_cbc(), key, Some(iv), from, to).await
 }
 
-/// Encrypt a slice to a `Vec<u8>` async
+/// Encrypt a buffered stream into another using a key. Returns the number of bytes written to `to`.
+///
+/// The stream's own buffer is encrypted directly, instead of being copied through another buffer like `encrypt_stream()` does.
+///
+/// # Notes
+/// `to` is flushed before returning.
 #[cfg(feature="async")] 
-pub async fn encrypt_slice(key: &AesKey, from: impl AsRef<[u8]>) -> Result<Vec<u8>, Error>
+#[inline] pub async fn encrypt_stream_buf<F,T>(key: &AesKey, from: &mut F, to: &mut T) -> Result<usize, Error>
+where F: AsyncBufRead + Unpin + ?Sized,
+      T: AsyncWrite + Unpin + ?Sized
 {
-    let from = from.as_ref();
-    let mut to = Vec::with_capacity(from.len() + 128);
-    
-    encrypt_stream(key, &mut &from[..], &mut to).await?;
-    Ok(to)
+    crypt_stream_buf(Mode::Encrypt, key, from, to).await
 }
 
-/// Encrypt a slice to a `Vec<u8>` sync
-pub fn encrypt_slice_sync(key: &AesKey, from: impl AsRef<[u8]>) -> Result<Vec<u8>, Error>
+/// Encrypt a stream into another using a key. Returns the number of bytes written to `to`.
+///
+/// # Notes
+/// `to` is flushed before returning.
+#[inline] pub fn encrypt_stream_sync<F,T>(key: &AesKey, from: &mut F, to: &mut T) -> Result<usize, Error>
+where F: io::Read + ?Sized,
+      T: io::Write + ?Sized
 {
-    let from = from.as_ref();
-    let mut to = Vec::with_capacity(from.len() + 128);
-    
-    encrypt_stream_sync(key, &mut &from[..], &mut to)?;
-    Ok(to)
+    encrypt_stream_sync_buffered(key, from, to, BUFFER_SIZE)
 }
 
-pub use crate::error::aes::Error;
+/// Encrypt a stream into another using a key, reading `buffer_size` bytes at a time. Returns the number of bytes written to `to`.
+///
+/// # Notes
+/// `to` is flushed before returning.
+/// Buffers larger than `BUFFER_SIZE` are allocated on the heap.
+///
+/// # Panics
+/// If `buffer_size` is 0.
+#[inline] pub fn encrypt_stream_sync_buffered<F,T>(key: &AesKey, from: &mut F, to: &mut T, buffer_size: usize) -> Result<usize, Error>
+where F: io::Read + ?Sized,
+      T: io::Write + ?Sized
+{
+    crypt_loop_sync(Mode::Encrypt, Cipher::aes_128_cbc(), &key.key[..], Some(&key.iv[..]), from, to, buffer_size)
+}
 
-#[cfg(test)]
-mod tests
+/// Encrypt a stream into another using a key and IV held elsewhere, the same way `encrypt_stream_sync()` does with an `AesKey`. Returns the number of bytes written to `to`.
+///
+/// # Notes
+/// `to` is flushed before returning.
+///
+/// # Errors
+/// `Error::Length` if `key` or `iv` are not the right size.
+pub fn encrypt_stream_raw_sync<F,T>(key: &[u8], iv: &[u8], from: &mut F, to: &mut T) -> Result<usize, Error>
+where F: io::Read + ?Sized,
+      T: io::Write + ?Sized
 {
-    #[test]
-    fn transmute_safe()
-    {
-	use std::mem::{size_of, align_of};
+    crypt_stream_with_sync(Mode::Encrypt, Cipher::aes_128_cbc(), key, Some(iv), from, to)
+}
 
-	assert_eq!(size_of::<super::AesKey>(), size_of::<[u8; super::KEYSIZE + super::IVSIZE]>());
-	assert_eq!(align_of::<super::AesKey>(), align_of::<[u8; super::KEYSIZE + super::IVSIZE]>());
-	let key = super::AesKey::generate().unwrap();
+/// Decrypt a stream into another using a key. Returns the number of bytes written to `to`.
+///
+/// # Notes
+/// `to` is flushed before returning.
+#[cfg(feature="async")] 
+#[inline] pub async fn decrypt_stream<F,T>(key: &AesKey, from: &mut F, to: &mut T) -> Result<usize, Error>
+where F: AsyncRead + Unpin + ?Sized,
+      T: AsyncWrite + Unpin + ?Sized
+{
+    crypt_loop(Mode::Decrypt, Cipher::aes_128_cbc(), &key.key[..], Some(&key.iv[..]), from, to, |_| ()).await
+}
 
-	let bytes = Vec::from(key.as_ref());
-	let tbytes = key.clone().into_bytes();
+/// Decrypt a stream into another using a key and IV held elsewhere, the same way `decrypt_stream()` does with an `AesKey`. Returns the number of bytes written to `to`.
+///
+/// # Notes
+/// `to` is flushed before returning.
+///
+/// # Errors
+/// `Error::Length` if `key` or `iv` are not the right size.
+#[cfg(feature="async")] 
+pub async fn decrypt_stream_raw<F,T>(key: &[u8], iv: &[u8], from: &mut F, to: &mut T) -> Result<usize, Error>
+where F: AsyncRead + Unpin + ?Sized,
+      T: AsyncWrite + Unpin + ?Sized
+{
+    crypt_stream_with(Mode::Decrypt, Cipher::aes_128_cbc(), key, Some(iv), from, to).await
+}
 
-	let nkey = super::AesKey::from_bytes(tbytes);
+/// Decrypt a buffered stream into another using a key. Returns the number of bytes written to `to`.
+///
+/// The stream's own buffer is decrypted directly, instead of being copied through another buffer like `decrypt_stream()` does.
+///
+/// # Notes
+/// `to` is flushed before returning.
+#[cfg(feature="async")] 
+#[inline] pub async fn decrypt_stream_buf<F,T>(key: &AesKey, from: &mut F, to: &mut T) -> Result<usize, Error>
+where F: AsyncBufRead + Unpin + ?Sized,
+      T: AsyncWrite + Unpin + ?Sized
+{
+    crypt_stream_buf(Mode::Decrypt, key, from, to).await
+}
 
-	assert_eq!(nkey, key);
+#[cfg(feature="async")]
+async fn crypt_stream_buf<F,T>(mode: Mode, key: &AesKey, from: &mut F, to: &mut T) -> Result<usize, Error>
+where F: AsyncBufRead + Unpin + ?Sized,
+      T: AsyncWrite + Unpin + ?Sized
+{
+    let mut done=0;
+    let mut total_read=0;
+
+    let mut crypter = Crypter::new(Cipher::aes_128_cbc(), mode, &key.key[..], Some(&key.iv[..]))?;
+    let mut crypt_buffer = [0u8; BUFFER_SIZE + BLOCKSIZE];
+    loop {
+	let buffer = from.fill_buf().await?;
+	if buffer.is_empty() {
+	    break;
+	}
+	let read = buffer.len();
+	for chunk in buffer.chunks(BUFFER_SIZE) {
+	    let bytes_encrypted = crypter.update(chunk, &mut crypt_buffer)?;
+	    to.write_all(&crypt_buffer[..bytes_encrypted]).await?;
+	    done += bytes_encrypted;
+	}
+	from.consume(read);
+	total_read += read;
+    }
+
+    let bytes_encrypted = finalize_crypter(&mut crypter, mode, BLOCKSIZE, total_read, &mut crypt_buffer)?;
+    to.write_all(&crypt_buffer[..bytes_encrypted]).await?;
+    to.flush().await?;
+
+    Ok(done + bytes_encrypted)
+}
+
+/// Decrypt a stream into another using a key. Returns the number of bytes written to `to`.
+///
+/// # Notes
+/// `to` is flushed before returning.
+#[inline] pub fn decrypt_stream_sync<F,T>(key: &AesKey, from: &mut F, to: &mut T) -> Result<usize, Error>
+where F: io::Read + ?Sized,
+      T: io::Write + ?Sized
+{
+    crypt_loop_sync(Mode::Decrypt, Cipher::aes_128_cbc(), &key.key[..], Some(&key.iv[..]), from, to, BUFFER_SIZE)
+}
+
+/// Decrypt a stream into another using a key and IV held elsewhere, the same way `decrypt_stream_sync()` does with an `AesKey`. Returns the number of bytes written to `to`.
+///
+/// # Notes
+/// `to` is flushed before returning.
+///
+/// # Errors
+/// `Error::Length` if `key` or `iv` are not the right size.
+pub fn decrypt_stream_raw_sync<F,T>(key: &[u8], iv: &[u8], from: &mut F, to: &mut T) -> Result<usize, Error>
+where F: io::Read + ?Sized,
+      T: io::Write + ?Sized
+{
+    crypt_stream_with_sync(Mode::Decrypt, Cipher::aes_128_cbc(), key, Some(iv), from, to)
+}
+
+/// Finalise a crypter in `mode` that has been given `total_read` bytes of input, for a cipher with `block_size` byte blocks.
+///
+/// # Errors
+/// When decrypting, `Error::Truncated` if a block cipher's ciphertext was not a whole number of blocks, and `Error::Decrypt` if its padding was invalid (the key is wrong, or the ciphertext was corrupted).
+fn finalize_crypter(crypter: &mut Crypter, mode: Mode, block_size: usize, total_read: usize, output: &mut [u8]) -> Result<usize, Error>
+{
+    match mode {
+	Mode::Encrypt => Ok(crypter.finalize(output)?),
+	Mode::Decrypt => {
+	    // Stream modes (CTR, CFB, OFB) have a block size of 1, so any length of ciphertext is whole
+	    if block_size > 1 && (total_read == 0 || !total_read.is_multiple_of(block_size)) {
+		return Err(Error::Truncated);
+	    }
+	    crypter.finalize(output).map_err(|_| Error::Decrypt)
+	},
+    }
+}
+
+/// A reader that decrypts the ciphertext read from an inner reader.
+///
+/// Implements `io::Read`, and `AsyncRead` with the `async` feature, when the inner reader does. Reading to the end yields the same plaintext `decrypt_stream()` would write.
+pub struct DecryptReader<R>
+{
+    inner: R,
+    /// `None` once the inner reader has ended and the crypter has been finalised
+    crypter: Option<Crypter>,
+    input: Box<[u8; BUFFER_SIZE]>,
+    output: Box<[u8; BUFFER_SIZE + BLOCKSIZE]>,
+    /// The range of `output` that has not been read yet
+    pos: usize,
+    len: usize,
+    /// The number of bytes of ciphertext read from `inner` so far
+    total_read: usize,
+}
+
+impl<R> DecryptReader<R>
+{
+    /// Wrap a reader of ciphertext encrypted with `key`
+    pub fn new(inner: R, key: &AesKey) -> Result<Self, Error>
+    {
+	Ok(Self {
+	    inner,
+	    crypter: Some(Crypter::new(Cipher::aes_128_cbc(), Mode::Decrypt, &key.key[..], Some(&key.iv[..]))?),
+	    input: Box::new([0u8; BUFFER_SIZE]),
+	    output: Box::new([0u8; BUFFER_SIZE + BLOCKSIZE]),
+	    pos: 0,
+	    len: 0,
+	    total_read: 0,
+	})
+    }
+
+    /// Consume into the inner reader
+    ///
+    /// # Notes
+    /// Any decrypted data that has not been read yet is lost.
+    #[inline] pub fn into_inner(self) -> R
+    {
+	self.inner
+    }
+
+    /// Copy as much pending plaintext into `buf` as will fit
+    fn drain(&mut self, buf: &mut [u8]) -> usize
+    {
+	let sz = bytes::copy_slice(buf, &self.output[self.pos..self.len]);
+	self.pos += sz;
+	sz
+    }
+
+    /// Decrypt `read` bytes of `input` into `output`. Finalises the crypter if `read` is 0.
+    ///
+    /// # Errors
+    /// `io::ErrorKind::InvalidData`, holding the `Error` from decrypting (e.g. `Error::Truncated`).
+    fn process(&mut self, read: usize) -> io::Result<()>
+    {
+	let crypter = match self.crypter.as_mut() {
+	    Some(crypter) => crypter,
+	    None => return Ok(()),
+	};
+	let len = if read == 0 {
+	    let len = finalize_crypter(crypter, Mode::Decrypt, BLOCKSIZE, self.total_read, &mut self.output[..]);
+	    self.crypter = None;
+	    len
+	} else {
+	    self.total_read += read;
+	    crypter.update(&self.input[..read], &mut self.output[..]).map_err(Error::from)
+	};
+	self.pos = 0;
+	self.len = 0;
+	self.len = len.map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+	Ok(())
+    }
+}
+
+impl<R> io::Read for DecryptReader<R>
+where R: io::Read
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize>
+    {
+	while !buf.is_empty() {
+	    if self.pos < self.len {
+		return Ok(self.drain(buf));
+	    }
+	    if self.crypter.is_none() {
+		break;
+	    }
+	    let read = self.inner.read(&mut self.input[..])?;
+	    self.process(read)?;
+	}
+	Ok(0)
+    }
+}
+
+#[cfg(feature="async")]
+impl<R> AsyncRead for DecryptReader<R>
+where R: AsyncRead + Unpin
+{
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>>
+    {
+	let this = self.get_mut();
+	while buf.remaining() > 0 {
+	    if this.pos < this.len {
+		let sz = this.drain(buf.initialize_unfilled());
+		buf.advance(sz);
+		break;
+	    }
+	    if this.crypter.is_none() {
+		break;
+	    }
+	    let read = {
+		let mut input = ReadBuf::new(&mut this.input[..]);
+		match Pin::new(&mut this.inner).poll_read(cx, &mut input) {
+		    Poll::Ready(Ok(())) => input.filled().len(),
+		    Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+		    Poll::Pending => return Poll::Pending,
+		}
+	    };
+	    this.process(read)?;
+	}
+	Poll::Ready(Ok(()))
+    }
+}
+
+/// A writer that encrypts everything written to it and writes the ciphertext to an inner writer.
+///
+/// # Notes
+/// The final block is only written on `shutdown()`. Dropping the writer before then leaves `inner` holding incomplete ciphertext that will not decrypt.
+/// The ciphertext is the same as what `encrypt_stream()` would write for the same plaintext.
+#[cfg(feature="async")]
+pub struct EncryptWriter<W>
+{
+    inner: W,
+    /// `None` once the crypter has been finalised on shutdown
+    crypter: Option<Crypter>,
+    output: Box<[u8; BUFFER_SIZE + BLOCKSIZE]>,
+    /// The range of `output` that has not been written to `inner` yet
+    pos: usize,
+    len: usize,
+}
+
+#[cfg(feature="async")]
+impl<W> EncryptWriter<W>
+{
+    /// Wrap a writer that ciphertext encrypted with `key` is written to
+    pub fn new(inner: W, key: &AesKey) -> Result<Self, Error>
+    {
+	Ok(Self {
+	    inner,
+	    crypter: Some(Crypter::new(Cipher::aes_128_cbc(), Mode::Encrypt, &key.key[..], Some(&key.iv[..]))?),
+	    output: Box::new([0u8; BUFFER_SIZE + BLOCKSIZE]),
+	    pos: 0,
+	    len: 0,
+	})
+    }
+
+    /// Consume into the inner writer
+    ///
+    /// # Notes
+    /// Any ciphertext that has not been written yet is lost.
+    #[inline] pub fn into_inner(self) -> W
+    {
+	self.inner
+    }
+}
+
+#[cfg(feature="async")]
+impl<W> EncryptWriter<W>
+where W: AsyncWrite + Unpin
+{
+    /// Write all pending ciphertext to the inner writer
+    fn poll_drain(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>>
+    {
+	while self.pos < self.len {
+	    match Pin::new(&mut self.inner).poll_write(cx, &self.output[self.pos..self.len]) {
+		Poll::Ready(Ok(0)) => return Poll::Ready(Err(io::Error::new(io::ErrorKind::WriteZero, "failed to write ciphertext"))),
+		Poll::Ready(Ok(written)) => self.pos += written,
+		Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+		Poll::Pending => return Poll::Pending,
+	    }
+	}
+	Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(feature="async")]
+impl<W> AsyncWrite for EncryptWriter<W>
+where W: AsyncWrite + Unpin
+{
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>>
+    {
+	let this = self.get_mut();
+	match this.poll_drain(cx) {
+	    Poll::Ready(Ok(())) => (),
+	    Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+	    Poll::Pending => return Poll::Pending,
+	}
+	let crypter = match this.crypter.as_mut() {
+	    Some(crypter) => crypter,
+	    None => return Poll::Ready(Err(io::Error::new(io::ErrorKind::BrokenPipe, "write after shutdown"))),
+	};
+	let chunk = &buf[..buf.len().min(BUFFER_SIZE)];
+	this.len = crypter.update(chunk, &mut this.output[..]).map_err(io::Error::other)?;
+	this.pos = 0;
+	Poll::Ready(Ok(chunk.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>>
+    {
+	let this = self.get_mut();
+	match this.poll_drain(cx) {
+	    Poll::Ready(Ok(())) => Pin::new(&mut this.inner).poll_flush(cx),
+	    Poll::Ready(Err(err)) => Poll::Ready(Err(err)),
+	    Poll::Pending => Poll::Pending,
+	}
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>>
+    {
+	let this = self.get_mut();
+	match this.poll_drain(cx) {
+	    Poll::Ready(Ok(())) => (),
+	    Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+	    Poll::Pending => return Poll::Pending,
+	}
+	// Only finalise once, the final block may take more than one poll to write
+	if let Some(mut crypter) = this.crypter.take() {
+	    this.len = crypter.finalize(&mut this.output[..]).map_err(io::Error::other)?;
+	    this.pos = 0;
+	    match this.poll_drain(cx) {
+		Poll::Ready(Ok(())) => (),
+		Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+		Poll::Pending => return Poll::Pending,
+	    }
+	}
+	Pin::new(&mut this.inner).poll_shutdown(cx)
+    }
+}
+
+/// A seekable reader that decrypts AES-CTR ciphertext read from an inner reader.
+///
+/// The ciphertext is what `encrypt_stream_with()` writes for `Cipher::aes_128_ctr()`, `aes_192_ctr()` or `aes_256_ctr()`, and must start at offset 0 of `inner`. Since CTR ciphertext is the same length as the plaintext, offsets in this reader are offsets in both.
+///
+/// # Seeking
+/// The counter block for any offset is the IV plus the offset's block number, so seeking only re-keys the crypter and discards the keystream for the part of the block before the new offset. Nothing before it is read.
+#[cfg(feature="async")]
+pub struct CtrReader<R>
+{
+    inner: R,
+    cipher: Cipher,
+    key: Zeroizing<Vec<u8>>,
+    iv: [u8; BLOCKSIZE],
+    crypter: Crypter,
+    input: Box<[u8; BUFFER_SIZE]>,
+    output: Box<[u8; BUFFER_SIZE + BLOCKSIZE]>,
+}
+
+#[cfg(feature="async")]
+impl<R> CtrReader<R>
+{
+    /// Wrap a reader of AES-CTR ciphertext. The cipher is picked from the size of `key`.
+    ///
+    /// # Errors
+    /// `Error::Length` if `key` is not 16, 24 or 32 bytes, or `iv` is not 16 bytes.
+    pub fn new(inner: R, key: impl AsRef<[u8]>, iv: impl AsRef<[u8]>) -> Result<Self, Error>
+    {
+	let (key, iv) = (key.as_ref(), iv.as_ref());
+	let cipher = match key.len() {
+	    16 => Cipher::aes_128_ctr(),
+	    24 => Cipher::aes_192_ctr(),
+	    32 => Cipher::aes_256_ctr(),
+	    got => return Err(Error::Length{expected: None, got: Some(got)}),
+	};
+	check_cipher(cipher, key, Some(iv))?;
+
+	let mut counter = [0u8; BLOCKSIZE];
+	bytes::copy_slice(&mut counter[..], iv);
+	Ok(Self {
+	    inner,
+	    cipher,
+	    key: Zeroizing::new(key.to_vec()),
+	    iv: counter,
+	    crypter: Crypter::new(cipher, Mode::Decrypt, key, Some(iv))?,
+	    input: Box::new([0u8; BUFFER_SIZE]),
+	    output: Box::new([0u8; BUFFER_SIZE + BLOCKSIZE]),
+	})
+    }
+
+    /// Consume into the inner reader
+    #[inline] pub fn into_inner(self) -> R
+    {
+	self.inner
+    }
+
+    /// Reset the crypter to decrypt from `offset`
+    fn reset(&mut self, offset: u64) -> io::Result<()>
+    {
+	let block = u128::from(offset / BLOCKSIZE as u64);
+	let counter = u128::from_be_bytes(self.iv).wrapping_add(block).to_be_bytes();
+
+	let mut crypter = Crypter::new(self.cipher, Mode::Decrypt, &self.key[..], Some(&counter[..]))
+	    .map_err(io::Error::other)?;
+	let skip = (offset % BLOCKSIZE as u64) as usize;
+	if skip > 0 {
+	    let mut discard = [0u8; BLOCKSIZE * 2];
+	    crypter.update(&[0u8; BLOCKSIZE][..skip], &mut discard[..])
+		.map_err(io::Error::other)?;
+	}
+	self.crypter = crypter;
+	Ok(())
+    }
+}
+
+#[cfg(feature="async")]
+impl<R> AsyncRead for CtrReader<R>
+where R: AsyncRead + Unpin
+{
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>>
+    {
+	let this = self.get_mut();
+	let max = buf.remaining().min(BUFFER_SIZE);
+	let read = {
+	    let mut input = ReadBuf::new(&mut this.input[..max]);
+	    match Pin::new(&mut this.inner).poll_read(cx, &mut input) {
+		Poll::Ready(Ok(())) => input.filled().len(),
+		Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+		Poll::Pending => return Poll::Pending,
+	    }
+	};
+	if read > 0 {
+	    let sz = this.crypter.update(&this.input[..read], &mut this.output[..])
+		.map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+	    buf.put_slice(&this.output[..sz]);
+	}
+	Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(feature="async")]
+impl<R> AsyncSeek for CtrReader<R>
+where R: AsyncSeek + Unpin
+{
+    #[inline] fn start_seek(self: Pin<&mut Self>, position: io::SeekFrom) -> io::Result<()>
+    {
+	Pin::new(&mut self.get_mut().inner).start_seek(position)
+    }
+
+    fn poll_complete(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<u64>>
+    {
+	let this = self.get_mut();
+	match Pin::new(&mut this.inner).poll_complete(cx) {
+	    Poll::Ready(Ok(offset)) => {
+		this.reset(offset)?;
+		Poll::Ready(Ok(offset))
+	    },
+	    other => other,
+	}
+    }
+}
+
+/// Check `cipher` is not an AEAD cipher, and `key` and `iv` are the right lengths for it
+fn check_cipher(cipher: Cipher, key: &[u8], iv: Option<&[u8]>) -> Result<(), Error>
+{
+    // The tag of an AEAD cipher is never produced or checked by the stream functions, so decryption would not be authenticated.
+    // openssl does not expose the cipher flags, so they are recognised by their mode.
+    let aead = cipher.nid().short_name().is_ok_and(|name| {
+	let name = name.to_ascii_lowercase();
+	["gcm", "ccm", "ocb", "siv", "poly1305"].iter().any(|mode| name.contains(mode))
+    });
+    if aead {
+	return Err(Error::Cipher);
+    }
+    if key.len() != cipher.key_len() {
+	return Err(Error::Length{expected: Some(cipher.key_len()), got: Some(key.len())});
+    }
+    match (cipher.iv_len(), iv) {
+	(Some(expected), Some(iv)) if iv.len() != expected => Err(Error::Length{expected: Some(expected), got: Some(iv.len())}),
+	(Some(expected), None) => Err(Error::Length{expected: Some(expected), got: None}),
+	(None, Some(iv)) => Err(Error::Length{expected: None, got: Some(iv.len())}),
+	_ => Ok(()),
+    }
+}
+
+#[cfg(feature="async")]
+async fn crypt_stream_with<F,T>(mode: Mode, cipher: Cipher, key: &[u8], iv: Option<&[u8]>, from: &mut F, to: &mut T) -> Result<usize, Error>
+where F: AsyncRead + Unpin + ?Sized,
+      T: AsyncWrite + Unpin + ?Sized
+{
+    check_cipher(cipher, key, iv)?;
+    crypt_loop(mode, cipher, key, iv, from, to, |_| ()).await
+}
+
+fn crypt_stream_with_sync<F,T>(mode: Mode, cipher: Cipher, key: &[u8], iv: Option<&[u8]>, from: &mut F, to: &mut T) -> Result<usize, Error>
+where F: io::Read + ?Sized,
+      T: io::Write + ?Sized
+{
+    check_cipher(cipher, key, iv)?;
+    crypt_loop_sync(mode, cipher, key, iv, from, to, BUFFER_SIZE)
+}
+
+/// Run a stream through `cipher` in `mode` into another, calling `progress` with the total number of bytes read from `from` so far after each chunk. Returns the number of bytes written to `to`.
+///
+/// # Notes
+/// `key` and `iv` are not checked against `cipher`, see `check_cipher()`.
+/// `to` is flushed before returning.
+#[cfg(feature="async")]
+async fn crypt_loop<F,T,P>(mode: Mode, cipher: Cipher, key: &[u8], iv: Option<&[u8]>, from: &mut F, to: &mut T, mut progress: P) -> Result<usize, Error>
+where F: AsyncRead + Unpin + ?Sized,
+      T: AsyncWrite + Unpin + ?Sized,
+      P: FnMut(usize)
+{
+    let mut read;
+    let mut done=0;
+    let mut total_read=0;
+
+    let mut crypter = Crypter::new(cipher, mode, key, iv)?;
+    let mut buffer = [0u8; BUFFER_SIZE];
+    let mut crypt_buffer = bytes::Buffer::<{BUFFER_SIZE + BLOCKSIZE}>::new(BUFFER_SIZE + cipher.block_size());
+    while {read = from.read(&mut buffer[..]).await?; read!=0} {
+	let bytes_encrypted = crypter.update(&buffer[..read], &mut crypt_buffer)?;
+	to.write_all(&crypt_buffer[..bytes_encrypted]).await?;
+	done += bytes_encrypted;
+	total_read += read;
+	progress(total_read);
+    }
+
+    let bytes_encrypted = finalize_crypter(&mut crypter, mode, cipher.block_size(), total_read, &mut crypt_buffer)?;
+    to.write_all(&crypt_buffer[..bytes_encrypted]).await?;
+    to.flush().await?;
+
+    Ok(done + bytes_encrypted)
+}
+
+/// Run a stream through `cipher` in `mode` into another, reading `buffer_size` bytes at a time. Returns the number of bytes written to `to`.
+///
+/// # Notes
+/// `key` and `iv` are not checked against `cipher`, see `check_cipher()`.
+/// `to` is flushed before returning.
+/// Buffers larger than `BUFFER_SIZE` are allocated on the heap.
+///
+/// # Panics
+/// If `buffer_size` is 0.
+fn crypt_loop_sync<F,T>(mode: Mode, cipher: Cipher, key: &[u8], iv: Option<&[u8]>, from: &mut F, to: &mut T, buffer_size: usize) -> Result<usize, Error>
+where F: io::Read + ?Sized,
+      T: io::Write + ?Sized
+{
+    assert!(buffer_size > 0, "buffer size cannot be 0");
+    let mut read;
+    let mut done=0;
+    let mut total_read=0;
+
+    let mut crypter = Crypter::new(cipher, mode, key, iv)?;
+    let mut buffer = bytes::Buffer::<BUFFER_SIZE>::new(buffer_size);
+    let mut crypt_buffer = bytes::Buffer::<{BUFFER_SIZE + BLOCKSIZE}>::new(buffer_size + cipher.block_size());
+    while {read = from.read(&mut buffer[..])?; read!=0} {
+	let bytes_encrypted = crypter.update(&buffer[..read], &mut crypt_buffer)?;
+	to.write_all(&crypt_buffer[..bytes_encrypted])?;
+	done += bytes_encrypted;
+	total_read += read;
+    }
+
+    let bytes_encrypted = finalize_crypter(&mut crypter, mode, cipher.block_size(), total_read, &mut crypt_buffer)?;
+    to.write_all(&crypt_buffer[..bytes_encrypted])?;
+    to.flush()?;
+
+    Ok(done + bytes_encrypted)
+}
+
+/// Encrypt a stream into another using any `Cipher`. Returns the number of bytes written to `to`.
+///
+/// # Notes
+/// `to` is flushed before returning.
+///
+/// # Errors
+/// `Error::Length` if `key` or `iv` are not the right size for `cipher`. `iv` must be `None` if `cipher` does not use one.
+/// `Error::Cipher` if `cipher` is an AEAD cipher (e.g. GCM), since its tag would not be produced.
+#[cfg(feature="async")] 
+#[inline] pub async fn encrypt_stream_with<F,T>(cipher: Cipher, key: &[u8], iv: Option<&[u8]>, from: &mut F, to: &mut T) -> Result<usize, Error>
+where F: AsyncRead + Unpin + ?Sized,
+      T: AsyncWrite + Unpin + ?Sized
+{
+    crypt_stream_with(Mode::Encrypt, cipher, key, iv, from, to).await
+}
+
+/// Encrypt a stream into another using any `Cipher`. Returns the number of bytes written to `to`.
+///
+/// # Notes
+/// `to` is flushed before returning.
+///
+/// # Errors
+/// `Error::Length` if `key` or `iv` are not the right size for `cipher`. `iv` must be `None` if `cipher` does not use one.
+/// `Error::Cipher` if `cipher` is an AEAD cipher (e.g. GCM), since its tag would not be produced.
+#[inline] pub fn encrypt_stream_with_sync<F,T>(cipher: Cipher, key: &[u8], iv: Option<&[u8]>, from: &mut F, to: &mut T) -> Result<usize, Error>
+where F: io::Read + ?Sized,
+      T: io::Write + ?Sized
+{
+    crypt_stream_with_sync(Mode::Encrypt, cipher, key, iv, from, to)
+}
+
+/// Decrypt a stream into another using any `Cipher`. Returns the number of bytes written to `to`.
+///
+/// # Notes
+/// `to` is flushed before returning.
+///
+/// # Errors
+/// `Error::Length` if `key` or `iv` are not the right size for `cipher`. `iv` must be `None` if `cipher` does not use one.
+/// `Error::Cipher` if `cipher` is an AEAD cipher (e.g. GCM), since its tag would not be checked.
+#[cfg(feature="async")] 
+#[inline] pub async fn decrypt_stream_with<F,T>(cipher: Cipher, key: &[u8], iv: Option<&[u8]>, from: &mut F, to: &mut T) -> Result<usize, Error>
+where F: AsyncRead + Unpin + ?Sized,
+      T: AsyncWrite + Unpin + ?Sized
+{
+    crypt_stream_with(Mode::Decrypt, cipher, key, iv, from, to).await
+}
+
+/// Decrypt a stream into another using any `Cipher`. Returns the number of bytes written to `to`.
+///
+/// # Notes
+/// `to` is flushed before returning.
+///
+/// # Errors
+/// `Error::Length` if `key` or `iv` are not the right size for `cipher`. `iv` must be `None` if `cipher` does not use one.
+/// `Error::Cipher` if `cipher` is an AEAD cipher (e.g. GCM), since its tag would not be checked.
+#[inline] pub fn decrypt_stream_with_sync<F,T>(cipher: Cipher, key: &[u8], iv: Option<&[u8]>, from: &mut F, to: &mut T) -> Result<usize, Error>
+where F: io::Read + ?Sized,
+      T: io::Write + ?Sized
+{
+    crypt_stream_with_sync(Mode::Decrypt, cipher, key, iv, from, to)
+}
+
+/// Decrypt a slice to a `Vec<u8>` async
+#[cfg(feature="async")] 
+pub async fn decrypt_slice(key: &AesKey, from: impl AsRef<[u8]>) -> Result<Vec<u8>, Error>
+{
+    let from = from.as_ref();
+    let mut to = Vec::with_capacity(from.len() + 128);
+    
+    decrypt_stream(key, &mut &from[..], &mut to).await?;
+    Ok(to)
+}
+
+/// Decrypt a slice to a `Vec<u8>` sync
+pub fn decrypt_slice_sync(key: &AesKey, from: impl AsRef<[u8]>) -> Result<Vec<u8>, Error>
+{
+    let from = from.as_ref();
+    let mut to = Vec::with_capacity(from.len() + 128);
+    
+    decrypt_stream_sync(key, &mut &from[..], &mut to)?;
+    Ok(to)
+}
+
+/// Encrypt a slice to a `Vec<u8>` async
+#[cfg(feature="async")] 
+pub async fn encrypt_slice(key: &AesKey, from: impl AsRef<[u8]>) -> Result<Vec<u8>, Error>
+{
+    let from = from.as_ref();
+    let mut to = Vec::with_capacity(from.len() + 128);
+    
+    encrypt_stream(key, &mut &from[..], &mut to).await?;
+    Ok(to)
+}
+
+/// Encrypt a slice to a `Vec<u8>` sync
+pub fn encrypt_slice_sync(key: &AesKey, from: impl AsRef<[u8]>) -> Result<Vec<u8>, Error>
+{
+    let from = from.as_ref();
+    let mut to = Vec::with_capacity(from.len() + 128);
+    
+    encrypt_stream_sync(key, &mut &from[..], &mut to)?;
+    Ok(to)
+}
+
+/// Encrypt and authenticate a slice into a single blob with AES-GCM.
+///
+/// The blob is `nonce || ciphertext || tag`, and can be decrypted with `open()` using the same key and `aad`.
+/// `aad` is additional data that is authenticated but not encrypted or stored in the blob, it can be empty.
+///
+/// # Notes
+/// Only the key part of `key` is used. A new random nonce is generated for every call, the IV is ignored.
+pub fn seal(key: &AesKey, plaintext: &[u8], aad: &[u8]) -> Result<Vec<u8>, Error>
+{
+    let mut nonce = [0u8; SEAL_NONCESIZE];
+    getrandom(&mut nonce[..])?;
+
+    let mut tag = [0u8; SEAL_TAGSIZE];
+    let ciphertext = symm::encrypt_aead(Cipher::aes_128_gcm(), key.k(), Some(&nonce[..]), aad, plaintext, &mut tag[..])
+	.map_err(|_| Error::Encrypt)?;
+
+    let mut output = Vec::with_capacity(SEAL_NONCESIZE + ciphertext.len() + SEAL_TAGSIZE);
+    output.extend_from_slice(&nonce[..]);
+    output.extend_from_slice(&ciphertext[..]);
+    output.extend_from_slice(&tag[..]);
+    Ok(output)
+}
+
+/// Verify and decrypt a blob created by `seal()`.
+///
+/// # Errors
+/// `Error::Length` if `blob` is too short to contain a nonce and tag.
+/// `Error::Decrypt` if the blob was not sealed with this key and `aad`, or has been modified.
+pub fn open(key: &AesKey, blob: &[u8], aad: &[u8]) -> Result<Vec<u8>, Error>
+{
+    if blob.len() < SEAL_NONCESIZE + SEAL_TAGSIZE {
+	return Err(Error::Length{expected: Some(SEAL_NONCESIZE + SEAL_TAGSIZE), got: Some(blob.len())});
+    }
+    let (nonce, rest) = blob.split_at(SEAL_NONCESIZE);
+    let (ciphertext, tag) = rest.split_at(rest.len() - SEAL_TAGSIZE);
+
+    symm::decrypt_aead(Cipher::aes_128_gcm(), key.k(), Some(nonce), aad, ciphertext, tag)
+	.map_err(|_| Error::Decrypt)
+}
+
+pub use crate::error::aes::Error;
+
+#[cfg(test)]
+mod tests
+{
+    #[test]
+    fn byte_layout()
+    {
+	const GOLDEN: [u8; 32] = [
+	    0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f,
+	    0xf0, 0xf1, 0xf2, 0xf3, 0xf4, 0xf5, 0xf6, 0xf7, 0xf8, 0xf9, 0xfa, 0xfb, 0xfc, 0xfd, 0xfe, 0xff,
+	];
+	let key = super::AesKey::new([0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f],
+				     [0xf0, 0xf1, 0xf2, 0xf3, 0xf4, 0xf5, 0xf6, 0xf7, 0xf8, 0xf9, 0xfa, 0xfb, 0xfc, 0xfd, 0xfe, 0xff]);
+
+	assert_eq!(key.clone().into_bytes(), GOLDEN);
+	assert_eq!(key.as_ref(), &GOLDEN[..]);
+	assert_eq!(super::AesKey::from_bytes(GOLDEN), key);
+	assert_eq!(super::AesKey::from_bytes(GOLDEN).k(), &GOLDEN[..16]);
+	assert_eq!(super::AesKey::from_bytes(GOLDEN).i(), &GOLDEN[16..]);
+    }
+
+    #[test]
+    fn hex_format()
+    {
+	let key = super::AesKey::new([0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d, 0x0e, 0x0f],
+				     [0xf0, 0xf1, 0xf2, 0xf3, 0xf4, 0xf5, 0xf6, 0xf7, 0xf8, 0xf9, 0xfa, 0xfb, 0xfc, 0xfd, 0xfe, 0xff]);
+
+	assert_eq!(format!("{:x}", key), "000102030405060708090a0b0c0d0e0ff0f1f2f3f4f5f6f7f8f9fafbfcfdfeff");
+	assert_eq!(format!("{:X}", key), "000102030405060708090A0B0C0D0E0FF0F1F2F3F4F5F6F7F8F9FAFBFCFDFEFF");
+	assert_eq!(format!("{}", key), "AesKey (Key: 000102030405060708090a0b0c0d0e0f, IV: f0f1f2f3f4f5f6f7f8f9fafbfcfdfeff)");
+    }
+
+    #[test]
+    fn stream_raw()
+    {
+	let data: Vec<u8> = (0..(super::BUFFER_SIZE * 2 + 21)).map(|i| i as u8).collect();
+	let key = super::AesKey::generate().unwrap();
+
+	let mut expected = Vec::new();
+	super::encrypt_stream_sync(&key, &mut &data[..], &mut expected).unwrap();
+	let mut encrypted = Vec::new();
+	super::encrypt_stream_raw_sync(key.k(), key.i(), &mut &data[..], &mut encrypted).unwrap();
+	assert_eq!(encrypted, expected);
+
+	let mut decrypted = Vec::new();
+	super::decrypt_stream_raw_sync(key.k(), key.i(), &mut &encrypted[..], &mut decrypted).unwrap();
+	assert_eq!(decrypted, data);
+
+	assert!(matches!(super::encrypt_stream_raw_sync(&key.k()[..8], key.i(), &mut &data[..], &mut Vec::new()),
+			 Err(super::Error::Length{expected: Some(super::KEYSIZE), got: Some(8)})));
+	assert!(matches!(super::decrypt_stream_raw_sync(key.k(), &[], &mut &encrypted[..], &mut Vec::new()),
+			 Err(super::Error::Length{expected: Some(super::IVSIZE), got: Some(0)})));
+    }
+
+    #[cfg(feature="async")]
+    #[tokio::test]
+    async fn stream_raw_async()
+    {
+	let data: Vec<u8> = (0..(super::BUFFER_SIZE * 2 + 21)).map(|i| i as u8).collect();
+	let key = super::AesKey::generate().unwrap();
+
+	let mut expected = Vec::new();
+	super::encrypt_stream(&key, &mut &data[..], &mut expected).await.unwrap();
+	let mut encrypted = Vec::new();
+	super::encrypt_stream_raw(key.k(), key.i(), &mut &data[..], &mut encrypted).await.unwrap();
+	assert_eq!(encrypted, expected);
+
+	let mut decrypted = Vec::new();
+	super::decrypt_stream_raw(key.k(), key.i(), &mut &encrypted[..], &mut decrypted).await.unwrap();
+	assert_eq!(decrypted, data);
+	assert!(super::encrypt_stream_raw(&key.k()[..8], key.i(), &mut &data[..], &mut Vec::new()).await.is_err());
+    }
+
+    #[test]
+    fn from_slice_length()
+    {
+	let key = [0xa5u8; super::KEYSIZE];
+	let iv = [0x5au8; super::IVSIZE];
+
+	assert_eq!(super::AesKey::from_slice(&key[..], &iv[..]).unwrap(), super::AesKey::new(key, iv));
+	assert!(matches!(super::AesKey::from_slice(&key[..5], &iv[..]),
+			 Err(super::Error::Length{expected: Some(super::KEYSIZE), got: Some(5)})));
+	assert!(matches!(super::AesKey::from_slice(&key[..], &iv[..9]),
+			 Err(super::Error::Length{expected: Some(super::IVSIZE), got: Some(9)})));
+    }
+
+    #[test]
+    fn transmute_safe()
+    {
+	use std::mem::{size_of, align_of};
+
+	assert_eq!(size_of::<super::AesKey>(), size_of::<[u8; super::KEYSIZE + super::IVSIZE]>());
+	assert_eq!(align_of::<super::AesKey>(), align_of::<[u8; super::KEYSIZE + super::IVSIZE]>());
+	let key = super::AesKey::generate().unwrap();
+
+	let bytes = Vec::from(key.as_ref());
+	let tbytes = key.clone().into_bytes();
+
+	let nkey = super::AesKey::from_bytes(tbytes);
+
+	assert_eq!(nkey, key);
 	assert_eq!(&bytes[..], &tbytes[..]);
 	assert_eq!(key.as_ref(), &tbytes[..]);
     }
+
+    #[test]
+    fn tagged_key()
+    {
+	use super::{AesAlgorithm, TaggedAesKey};
+	let key = TaggedAesKey::generate(AesAlgorithm::Aes256Cbc).unwrap();
+	assert_eq!(key.k().len(), 32);
+	assert_eq!(format!("{:?}", key), "TaggedAesKey { algorithm: Aes256Cbc, .. }");
+
+	let data: Vec<u8> = (0..super::BUFFER_SIZE * 3 + 17).map(|x| x as u8).collect();
+	let mut encrypted = Vec::new();
+	super::encrypt_stream_tagged_sync(&key, &mut &data[..], &mut encrypted).unwrap();
+
+	let mut expected = Vec::new();
+	super::encrypt_stream_with_sync(super::Cipher::aes_256_cbc(), key.k(), Some(key.i()), &mut &data[..], &mut expected).unwrap();
+	assert_eq!(encrypted, expected);
+
+	let read = TaggedAesKey::from_bytes(key.to_bytes()).unwrap();
+	assert_eq!(read.algorithm(), AesAlgorithm::Aes256Cbc);
+	assert_eq!(read, key);
+
+	let mut decrypted = Vec::new();
+	super::decrypt_stream_tagged_sync(&read, &mut &encrypted[..], &mut decrypted).unwrap();
+	assert_eq!(decrypted, data);
+
+	let plain = super::AesKey::generate().unwrap();
+	let tagged = TaggedAesKey::from(plain.clone());
+	assert_eq!(tagged.algorithm(), AesAlgorithm::Aes128Cbc);
+	assert_eq!(super::encrypt_slice_sync(&plain, &data[..]).unwrap(), {
+	    let mut output = Vec::new();
+	    super::encrypt_stream_tagged_sync(&tagged, &mut &data[..], &mut output).unwrap();
+	    output
+	});
+
+	assert!(matches!(TaggedAesKey::from_slice(AesAlgorithm::Aes256Cbc, plain.k(), plain.i()), Err(super::Error::Length{expected: Some(32), got: Some(16)})));
+	assert!(TaggedAesKey::from_bytes(&key.to_bytes()[1..]).is_err());
+	assert!(TaggedAesKey::from_bytes(&key.to_bytes()[..33]).is_err());
+	assert!(TaggedAesKey::from_bytes([]).is_err());
+    }
+
+    #[test]
+    fn seal_open()
+    {
+	let key = super::AesKey::generate().unwrap();
+	let data = b"some data that needs to be kept secret and unmodified";
+
+	let blob = super::seal(&key, &data[..], b"header").unwrap();
+	assert_eq!(blob.len(), super::SEAL_NONCESIZE + data.len() + super::SEAL_TAGSIZE);
+	assert_eq!(super::open(&key, &blob[..], b"header").unwrap(), &data[..]);
+	assert_ne!(super::seal(&key, &data[..], b"header").unwrap(), blob);
+
+	let mut flipped = blob.clone();
+	flipped[super::SEAL_NONCESIZE + 3] ^= 1;
+	assert!(matches!(super::open(&key, &flipped[..], b"header"), Err(super::Error::Decrypt)));
+	assert!(matches!(super::open(&key, &blob[..], b"other"), Err(super::Error::Decrypt)));
+	assert!(matches!(super::open(&super::AesKey::generate().unwrap(), &blob[..], b"header"), Err(super::Error::Decrypt)));
+	assert!(matches!(super::open(&key, &blob[..super::SEAL_TAGSIZE], b"header"), Err(super::Error::Length{..})));
+
+	let empty = super::seal(&key, &[], &[]).unwrap();
+	assert!(super::open(&key, &empty[..], &[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn into_bytes_zeroizing()
+    {
+	let key = super::AesKey::generate().unwrap();
+	let bytes: super::Zeroizing<[u8; super::KEYSIZE + super::IVSIZE]> = key.clone().into_bytes_zeroizing();
+
+	assert_eq!(&bytes[..], key.as_ref());
+	assert_eq!(super::AesKey::from_bytes(*bytes), key);
+    }
+
+    #[test]
+    fn debug_redacted()
+    {
+	let key = super::AesKey::generate().unwrap();
+	assert_eq!(format!("{:?}", key), "AesKey { .. }");
+	assert_eq!(format!("{:?}", Some(&key)), "Some(AesKey { .. })");
+    }
+
+    #[test]
+    fn generate_with_rng()
+    {
+	use rand_chacha::{ChaCha20Rng, rand_core::SeedableRng};
+
+	let key = super::AesKey::generate_with_rng(ChaCha20Rng::seed_from_u64(1)).unwrap();
+	assert_eq!(super::AesKey::generate_with_rng(ChaCha20Rng::seed_from_u64(1)).unwrap(), key);
+	assert_ne!(super::AesKey::generate_with_rng(ChaCha20Rng::seed_from_u64(2)).unwrap(), key);
+
+	let mut rng = ChaCha20Rng::seed_from_u64(1);
+	let first = super::AesKey::generate_with_rng(&mut rng).unwrap();
+	assert_eq!(first, key);
+	assert_ne!(super::AesKey::generate_with_rng(&mut rng).unwrap(), first);
+    }
+
+    #[test]
+    fn stream_sync()
+    {
+	let key = super::AesKey::generate().unwrap();
+	let data = b"some plaintext that is not block aligned";
+
+	let mut encrypted = Vec::new();
+	super::encrypt_stream_sync(&key, &mut &data[..], &mut encrypted).unwrap();
+	let mut decrypted = Vec::new();
+	super::decrypt_stream_sync(&key, &mut &encrypted[..], &mut decrypted).unwrap();
+
+	assert_eq!(&decrypted[..], &data[..]);
+    }
+
+    #[test]
+    fn stream_sync_buffered()
+    {
+	let key = super::AesKey::generate().unwrap();
+	let data = vec![0x5au8; super::BUFFER_SIZE * 20 + 3];
+
+	let mut encrypted = Vec::new();
+	super::encrypt_stream_sync(&key, &mut &data[..], &mut encrypted).unwrap();
+	let mut buffered = Vec::new();
+	super::encrypt_stream_sync_buffered(&key, &mut &data[..], &mut buffered, 65536).unwrap();
+
+	assert_eq!(buffered, encrypted);
+    }
+
+    #[test]
+    fn try_from_slice()
+    {
+	use std::convert::TryFrom;
+	let key = super::AesKey::generate().unwrap();
+	let mut bytes = Vec::from(key.as_ref());
+
+	assert_eq!(super::AesKey::try_from(&bytes[..]).unwrap(), key);
+	assert!(super::AesKey::try_from(&bytes[..bytes.len()-1]).is_err());
+	bytes.push(0);
+	assert!(super::AesKey::try_from(&bytes[..]).is_err());
+    }
+
+    #[test]
+    fn stream_sync_flushed()
+    {
+	let key = super::AesKey::generate().unwrap();
+	let data = b"some plaintext that is not block aligned";
+
+	let mut writer = std::io::BufWriter::with_capacity(1024, Vec::new());
+	let written = super::encrypt_stream_sync(&key, &mut &data[..], &mut writer).unwrap();
+	assert_eq!(writer.get_ref().len(), written);
+
+	let encrypted = writer.into_inner().unwrap();
+	let mut writer = std::io::BufWriter::with_capacity(1024, Vec::new());
+	let written = super::decrypt_stream_sync(&key, &mut &encrypted[..], &mut writer).unwrap();
+	assert_eq!(written, data.len());
+	assert_eq!(&writer.get_ref()[..], &data[..]);
+    }
+
+    #[cfg(feature="async")]
+    #[tokio::test]
+    async fn stream_with_progress()
+    {
+	let key = super::AesKey::generate().unwrap();
+	let data: Vec<u8> = (0..super::BUFFER_SIZE * 3 + 17).map(|x| x as u8).collect();
+
+	let mut calls = Vec::new();
+	let mut encrypted = Vec::new();
+	let written = super::encrypt_stream_with_progress(&key, &mut &data[..], &mut encrypted, |total| calls.push(total)).await.unwrap();
+	assert_eq!(written, encrypted.len());
+	assert!(calls.len() >= 4);
+	assert!(calls.windows(2).all(|w| w[0] < w[1]));
+	assert_eq!(calls.last(), Some(&data.len()));
+
+	let mut decrypted = Vec::new();
+	super::decrypt_stream(&key, &mut &encrypted[..], &mut decrypted).await.unwrap();
+	assert_eq!(decrypted, data);
+
+	let mut calls = 0;
+	super::encrypt_stream_with_progress(&key, &mut &[][..], &mut Vec::new(), |_| calls += 1).await.unwrap();
+	assert_eq!(calls, 0);
+    }
+
+    #[test]
+    fn stream_truncated()
+    {
+	let key = super::AesKey::new([0x11u8; super::KEYSIZE], [0x22u8; super::IVSIZE]);
+	let data: Vec<u8> = (0..super::BUFFER_SIZE + 100).map(|x| x as u8).collect();
+	let encrypted = super::encrypt_slice_sync(&key, &data[..]).unwrap();
+
+	for &len in [0, 1, super::BLOCKSIZE - 1, encrypted.len() - 1, encrypted.len() - super::BLOCKSIZE + 3].iter() {
+	    assert!(matches!(super::decrypt_stream_sync(&key, &mut &encrypted[..len], &mut Vec::new()), Err(super::Error::Truncated)), "length {}", len);
+	}
+
+	let wrong = super::AesKey::new([0x33u8; super::KEYSIZE], [0x22u8; super::IVSIZE]);
+	assert!(matches!(super::decrypt_stream_sync(&wrong, &mut &encrypted[..], &mut Vec::new()), Err(super::Error::Decrypt)));
+
+	let mut decrypted = Vec::new();
+	super::decrypt_stream_sync(&key, &mut &encrypted[..], &mut decrypted).unwrap();
+	assert_eq!(decrypted, data);
+    }
+
+    #[test]
+    fn stream_with_truncated()
+    {
+	use super::Cipher;
+	let data: Vec<u8> = (0..super::BUFFER_SIZE + 100).map(|x| x as u8).collect();
+	let key = [0x11u8; 32];
+	let iv = [0x22u8; 16];
+
+	let mut encrypted = Vec::new();
+	super::encrypt_stream_with_sync(Cipher::aes_256_cbc(), &key[..], Some(&iv[..]), &mut &data[..], &mut encrypted).unwrap();
+	for &len in [0, 1, encrypted.len() - 1].iter() {
+	    assert!(matches!(super::decrypt_stream_with_sync(Cipher::aes_256_cbc(), &key[..], Some(&iv[..]), &mut &encrypted[..len], &mut Vec::new()),
+			     Err(super::Error::Truncated)), "length {}", len);
+	}
+	assert!(matches!(super::decrypt_stream_with_sync(Cipher::aes_256_cbc(), &[0x33u8; 32][..], Some(&iv[..]), &mut &encrypted[..], &mut Vec::new()),
+			 Err(super::Error::Decrypt)));
+
+	// Any length of CTR ciphertext is whole
+	let mut encrypted = Vec::new();
+	super::encrypt_stream_with_sync(Cipher::aes_256_ctr(), &key[..], Some(&iv[..]), &mut &data[..], &mut encrypted).unwrap();
+	let mut decrypted = Vec::new();
+	super::decrypt_stream_with_sync(Cipher::aes_256_ctr(), &key[..], Some(&iv[..]), &mut &encrypted[..7], &mut decrypted).unwrap();
+	assert_eq!(&decrypted[..], &data[..7]);
+    }
+
+    #[cfg(feature="async")]
+    #[tokio::test]
+    async fn stream_truncated_async()
+    {
+	let key = super::AesKey::new([0x11u8; super::KEYSIZE], [0x22u8; super::IVSIZE]);
+	let data: Vec<u8> = (0..super::BUFFER_SIZE + 100).map(|x| x as u8).collect();
+	let encrypted = super::encrypt_slice_sync(&key, &data[..]).unwrap();
+
+	assert!(matches!(super::decrypt_stream(&key, &mut &encrypted[..encrypted.len() - 1], &mut Vec::new()).await, Err(super::Error::Truncated)));
+	assert!(matches!(super::decrypt_stream_buf(&key, &mut &encrypted[..encrypted.len() - 1], &mut Vec::new()).await, Err(super::Error::Truncated)));
+	assert!(matches!(super::decrypt_stream_with(super::Cipher::aes_128_cbc(), key.k(), Some(key.i()), &mut &encrypted[..encrypted.len() - 1], &mut Vec::new()).await,
+			 Err(super::Error::Truncated)));
+    }
+
+    #[cfg(feature="async")]
+    #[tokio::test]
+    async fn stream_flushed()
+    {
+	let key = super::AesKey::generate().unwrap();
+	let data = b"some plaintext that is not block aligned";
+
+	let mut writer = tokio::io::BufWriter::with_capacity(1024, Vec::new());
+	let written = super::encrypt_stream(&key, &mut &data[..], &mut writer).await.unwrap();
+	assert_eq!(writer.get_ref().len(), written);
+
+	let encrypted = writer.into_inner();
+	let mut writer = tokio::io::BufWriter::with_capacity(1024, Vec::new());
+	let written = super::decrypt_stream(&key, &mut &encrypted[..], &mut writer).await.unwrap();
+	assert_eq!(written, data.len());
+	assert_eq!(&writer.get_ref()[..], &data[..]);
+    }
+
+    #[cfg(feature="password")]
+    #[test]
+    fn derive_from_password()
+    {
+	use crate::password::Salt;
+	let salt = Salt::random().unwrap();
+
+	let key = super::AesKey::derive_from_password("passphrase", &salt);
+	assert_eq!(super::AesKey::derive_from_password("passphrase", &salt), key);
+	assert_ne!(super::AesKey::derive_from_password("passphrase", &Salt::random().unwrap()), key);
+	assert_ne!(super::AesKey::derive_from_password("other passphrase", &salt), key);
+	assert_ne!(key.k(), key.i());
+
+	let hash = crate::password::Password::derive("passphrase", &salt);
+	assert_ne!(key.k(), &hash.as_ref()[..super::KEYSIZE]);
+    }
+
+    #[test]
+    fn regenerate()
+    {
+	let mut key = super::AesKey::generate().unwrap();
+	let old = key.clone();
+
+	key.regenerate().unwrap();
+	let first = key.clone();
+	assert_ne!(first, old);
+
+	key.regenerate().unwrap();
+	assert_ne!(key.k(), first.k());
+	assert_ne!(key.i(), first.i());
+    }
+
+    #[test]
+    fn stream_with_cipher()
+    {
+	use super::Cipher;
+	let data = b"some plaintext that is not block aligned";
+
+	let key = [0x11u8; 32];
+	let iv = [0x22u8; 16];
+	for (cipher, key) in [(Cipher::aes_256_cbc(), &key[..]), (Cipher::aes_128_ctr(), &key[..16])].iter().copied() {
+	    let mut encrypted = Vec::new();
+	    super::encrypt_stream_with_sync(cipher, key, Some(&iv[..]), &mut &data[..], &mut encrypted).unwrap();
+	    assert_ne!(&encrypted[..], &data[..]);
+
+	    let mut decrypted = Vec::new();
+	    super::decrypt_stream_with_sync(cipher, key, Some(&iv[..]), &mut &encrypted[..], &mut decrypted).unwrap();
+	    assert_eq!(&decrypted[..], &data[..]);
+	}
+
+	// Same as the fixed aes-128-cbc functions
+	let aes = super::AesKey::generate().unwrap();
+	let mut expected = Vec::new();
+	super::encrypt_stream_sync(&aes, &mut &data[..], &mut expected).unwrap();
+	let mut encrypted = Vec::new();
+	super::encrypt_stream_with_sync(Cipher::aes_128_cbc(), aes.k(), Some(aes.i()), &mut &data[..], &mut encrypted).unwrap();
+	assert_eq!(encrypted, expected);
+
+	assert!(matches!(super::encrypt_stream_with_sync(Cipher::aes_256_cbc(), &key[..16], Some(&iv[..]), &mut &data[..], &mut Vec::new()),
+			 Err(super::Error::Length{expected: Some(32), got: Some(16)})));
+	assert!(matches!(super::encrypt_stream_with_sync(Cipher::aes_256_cbc(), &key[..], None, &mut &data[..], &mut Vec::new()),
+			 Err(super::Error::Length{expected: Some(16), got: None})));
+	assert!(matches!(super::encrypt_stream_with_sync(Cipher::aes_128_ecb(), &key[..16], Some(&iv[..]), &mut &data[..], &mut Vec::new()),
+			 Err(super::Error::Length{expected: None, got: Some(16)})));
+
+	// The tag would be lost
+	for cipher in [Cipher::aes_128_gcm(), Cipher::aes_256_gcm(), Cipher::aes_128_ccm()].iter().copied() {
+	    assert!(matches!(super::encrypt_stream_with_sync(cipher, &key[..cipher.key_len()], Some(&iv[..12]), &mut &data[..], &mut Vec::new()),
+			     Err(super::Error::Cipher)));
+	    assert!(matches!(super::decrypt_stream_with_sync(cipher, &key[..cipher.key_len()], Some(&iv[..12]), &mut &data[..], &mut Vec::new()),
+			     Err(super::Error::Cipher)));
+	}
+    }
+
+    #[cfg(feature="async")]
+    #[tokio::test]
+    async fn stream_with_cipher_async()
+    {
+	use super::Cipher;
+	let data = b"some plaintext that is not block aligned";
+	let key = [0x11u8; 16];
+	let iv = [0x22u8; 16];
+
+	let mut encrypted = Vec::new();
+	super::encrypt_stream_with(Cipher::aes_128_ctr(), &key[..], Some(&iv[..]), &mut &data[..], &mut encrypted).await.unwrap();
+	let mut decrypted = Vec::new();
+	super::decrypt_stream_with(Cipher::aes_128_ctr(), &key[..], Some(&iv[..]), &mut &encrypted[..], &mut decrypted).await.unwrap();
+	assert_eq!(&decrypted[..], &data[..]);
+    }
+
+    #[cfg(feature="async")]
+    #[tokio::test]
+    async fn ctr_reader_seek()
+    {
+	use super::{Cipher, CtrReader};
+	use std::io::{Cursor, SeekFrom};
+	use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+	let data: Vec<u8> = (0..(super::BUFFER_SIZE * 3 + 37)).map(|i| (i % 251) as u8).collect();
+	let key = [0x11u8; 32];
+	let iv = [0xffu8; 16];
+
+	let mut encrypted = Vec::new();
+	super::encrypt_stream_with_sync(Cipher::aes_256_ctr(), &key[..], Some(&iv[..]), &mut &data[..], &mut encrypted).unwrap();
+	assert_eq!(encrypted.len(), data.len());
+
+	let mut reader = CtrReader::new(Cursor::new(encrypted), &key[..], &iv[..]).unwrap();
+	let mut decrypted = Vec::new();
+	reader.read_to_end(&mut decrypted).await.unwrap();
+	assert_eq!(&decrypted[..], &data[..]);
+
+	for &offset in [0, 5, 16, 1000, super::BUFFER_SIZE + 3, data.len() - 1, data.len()].iter() {
+	    assert_eq!(reader.seek(SeekFrom::Start(offset as u64)).await.unwrap(), offset as u64);
+	    let mut decrypted = Vec::new();
+	    reader.read_to_end(&mut decrypted).await.unwrap();
+	    assert_eq!(&decrypted[..], &data[offset..]);
+	}
+
+	reader.seek(SeekFrom::End(-21)).await.unwrap();
+	let mut decrypted = [0u8; 7];
+	reader.read_exact(&mut decrypted[..]).await.unwrap();
+	assert_eq!(&decrypted[..], &data[data.len()-21..][..7]);
+	reader.seek(SeekFrom::Current(3)).await.unwrap();
+	reader.read_exact(&mut decrypted[..]).await.unwrap();
+	assert_eq!(&decrypted[..], &data[data.len()-11..][..7]);
+
+	assert!(CtrReader::new(&b""[..], &key[..20], &iv[..]).is_err());
+	assert!(CtrReader::new(&b""[..], &key[..16], &iv[..8]).is_err());
+    }
+
+    #[test]
+    fn decrypt_reader_sync()
+    {
+	use std::io::Read;
+	let key = super::AesKey::generate().unwrap();
+	let data: Vec<u8> = (0..super::BUFFER_SIZE * 3 + 17).map(|x| x as u8).collect();
+
+	let mut encrypted = Vec::new();
+	super::encrypt_stream_sync(&key, &mut &data[..], &mut encrypted).unwrap();
+
+	let mut reader = super::DecryptReader::new(&encrypted[..], &key).unwrap();
+	let mut decrypted = Vec::new();
+	reader.read_to_end(&mut decrypted).unwrap();
+	assert_eq!(decrypted, data);
+	assert_eq!(reader.read(&mut [0u8; 16]).unwrap(), 0);
+
+	let mut reader = super::DecryptReader::new(&encrypted[..encrypted.len()-1], &key).unwrap();
+	let err = reader.read_to_end(&mut Vec::new()).unwrap_err();
+	assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+	assert!(matches!(err.get_ref().and_then(|err| err.downcast_ref::<super::Error>()), Some(super::Error::Truncated)));
+
+	let wrong = super::AesKey::generate().unwrap();
+	let err = super::DecryptReader::new(&encrypted[..], &wrong).unwrap().read_to_end(&mut Vec::new()).unwrap_err();
+	assert!(matches!(err.get_ref().and_then(|err| err.downcast_ref::<super::Error>()), Some(super::Error::Decrypt)));
+    }
+
+    #[cfg(feature="async")]
+    #[tokio::test]
+    async fn stream_buf()
+    {
+	use tokio::io::BufReader;
+	let key = super::AesKey::generate().unwrap();
+	let data: Vec<u8> = (0..super::BUFFER_SIZE * 3 + 17).map(|x| x as u8).collect();
+
+	let mut encrypted = Vec::new();
+	super::encrypt_stream(&key, &mut &data[..], &mut encrypted).await.unwrap();
+
+	for &capacity in [7, super::BUFFER_SIZE * 2 + 1].iter() {
+	    let mut buffered = Vec::new();
+	    assert_eq!(super::encrypt_stream_buf(&key, &mut BufReader::with_capacity(capacity, &data[..]), &mut buffered).await.unwrap(), encrypted.len());
+	    assert_eq!(buffered, encrypted);
+
+	    let mut decrypted = Vec::new();
+	    assert_eq!(super::decrypt_stream_buf(&key, &mut BufReader::with_capacity(capacity, &encrypted[..]), &mut decrypted).await.unwrap(), data.len());
+	    assert_eq!(decrypted, data);
+	}
+    }
+
+    #[cfg(feature="async")]
+    #[tokio::test]
+    async fn decrypt_reader()
+    {
+	use tokio::io::AsyncReadExt;
+	let key = super::AesKey::generate().unwrap();
+	let data: Vec<u8> = (0..super::BUFFER_SIZE * 3 + 17).map(|x| x as u8).collect();
+
+	let mut encrypted = Vec::new();
+	super::encrypt_stream(&key, &mut &data[..], &mut encrypted).await.unwrap();
+
+	let mut reader = super::DecryptReader::new(&encrypted[..], &key).unwrap();
+	let mut decrypted = Vec::new();
+	reader.read_to_end(&mut decrypted).await.unwrap();
+	assert_eq!(decrypted, data);
+    }
+
+    #[cfg(feature="async")]
+    #[tokio::test]
+    async fn encrypt_writer()
+    {
+	use tokio::io::AsyncWriteExt;
+	let key = super::AesKey::generate().unwrap();
+	let data: Vec<u8> = (0..super::BUFFER_SIZE * 3 + 17).map(|x| x as u8).collect();
+
+	let mut expected = Vec::new();
+	super::encrypt_stream(&key, &mut &data[..], &mut expected).await.unwrap();
+
+	let mut writer = super::EncryptWriter::new(Vec::new(), &key).unwrap();
+	for chunk in data.chunks(1000) {
+	    writer.write_all(chunk).await.unwrap();
+	}
+	writer.flush().await.unwrap();
+	writer.shutdown().await.unwrap();
+	writer.shutdown().await.unwrap();
+	assert!(writer.write_all(b"more").await.is_err());
+
+	let encrypted = writer.into_inner();
+	assert_eq!(encrypted, expected);
+	assert_eq!(super::decrypt_slice_sync(&key, &encrypted[..]).unwrap(), data);
+
+	let mut writer = super::EncryptWriter::new(Vec::new(), &key).unwrap();
+	writer.shutdown().await.unwrap();
+	assert_eq!(super::decrypt_slice_sync(&key, writer.into_inner()).unwrap(), b"");
+    }
 }
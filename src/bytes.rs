@@ -1,8 +1,13 @@
 //! Bytes related utils
 use libc::c_void;
-use std::{
+#[allow(unused_imports)]
+use core::{
     slice,
     mem,
+    ops::{
+	Deref,
+	DerefMut,
+    },
 };
 
 /// Copy slice of bytes only. To copy generic slice, use `util::copy_slice()`.
@@ -11,9 +16,12 @@ use std::{
 /// `dst` and `src` must not overlap. See [move_slice].
 pub fn copy_slice(dst: &mut [u8], src: &[u8]) -> usize
 {
-    let sz = std::cmp::min(dst.len(),src.len());
+    let sz = core::cmp::min(dst.len(),src.len());
+    if sz == 0 {
+	return 0;
+    }
     unsafe {
-	libc::memcpy(&mut dst[0] as *mut u8 as *mut c_void, &src[0] as *const u8 as *const c_void, sz);
+	libc::memcpy(dst.as_mut_ptr() as *mut c_void, src.as_ptr() as *const c_void, sz);
     }
     sz
 }
@@ -24,13 +32,91 @@ pub fn copy_slice(dst: &mut [u8], src: &[u8]) -> usize
 /// `dst` and `src` can overlap.
 pub fn move_slice(dst: &mut [u8], src: &[u8]) -> usize
 {
-    let sz = std::cmp::min(dst.len(),src.len());
+    let sz = core::cmp::min(dst.len(),src.len());
+    if sz == 0 {
+	return 0;
+    }
     unsafe {
-	libc::memmove(&mut dst[0] as *mut u8 as *mut c_void, &src[0] as *const u8 as *const c_void, sz);
+	libc::memmove(dst.as_mut_ptr() as *mut c_void, src.as_ptr() as *const c_void, sz);
     }
     sz
 }
 
+/// Compare two byte slices for equality in constant time.
+///
+/// # Notes
+/// Only the contents are compared in constant time. Slices of different lengths are never equal, and the lengths are not treated as secret.
+pub fn ct_eq(a: &[u8], b: &[u8]) -> bool
+{
+    use subtle::ConstantTimeEq;
+    a.ct_eq(b).into()
+}
+
+/// Encode bytes as a lowercase hex string
+#[cfg(feature="std")]
+pub fn to_hex(bytes: &[u8]) -> String
+{
+    use std::fmt::Write;
+    let mut output = String::with_capacity(bytes.len() * 2);
+    for byte in bytes.iter() {
+	write!(output, "{:02x}", byte).unwrap();
+    }
+    output
+}
+
+/// Write bytes to a formatter as zero-padded hex, uppercase if `upper`. The alternate flag (`{:#x}`) adds a `0x` prefix.
+pub fn fmt_hex(bytes: &[u8], f: &mut core::fmt::Formatter<'_>, upper: bool) -> core::fmt::Result
+{
+    if f.alternate() {
+	f.write_str("0x")?;
+    }
+    for byte in bytes.iter() {
+	if upper {
+	    write!(f, "{:02X}", byte)?;
+	} else {
+	    write!(f, "{:02x}", byte)?;
+	}
+    }
+    Ok(())
+}
+
+fn hex_digit(c: u8) -> Option<u8>
+{
+    (c as char).to_digit(16).map(|d| d as u8)
+}
+
+/// Decode a hex string. Returns `None` if it is not valid hex.
+#[cfg(feature="std")]
+pub fn from_hex(string: &str) -> Option<Vec<u8>>
+{
+    let string = string.as_bytes();
+    if !string.len().is_multiple_of(2) {
+	return None;
+    }
+    string.chunks(2)
+	.map(|pair| Some((hex_digit(pair[0])? << 4) | hex_digit(pair[1])?))
+	.collect()
+}
+
+/// Decode a hex string into `output` without allocating. Returns `false` if it is not valid hex or does not decode to exactly `output.len()` bytes.
+///
+/// # Notes
+/// The contents of `output` are unspecified if this fails.
+pub fn from_hex_into(string: &str, output: &mut [u8]) -> bool
+{
+    let string = string.as_bytes();
+    if string.len() != output.len() * 2 {
+	return false;
+    }
+    for (byte, pair) in output.iter_mut().zip(string.chunks(2)) {
+	match (hex_digit(pair[0]), hex_digit(pair[1])) {
+	    (Some(high), Some(low)) => *byte = (high << 4) | low,
+	    _ => return false,
+	}
+    }
+    true
+}
+
 /// Get the bytes of a value
 pub fn refer<T: ?Sized>(value: &T) -> &[u8]
 {
@@ -68,6 +154,9 @@ pub unsafe fn derefer_unchecked_mut<T>(bytes: &mut [u8]) -> *mut T
 }
 
 /// Get a type from its bytes
+///
+/// # Notes
+/// The returned pointer is not guaranteed to be aligned for `T`. Use `read_unaligned()` on it, or copy the value out with `read_struct()`.
 pub fn derefer<T>(bytes: &[u8]) -> *const T
 {
     assert!(bytes.len() >= mem::size_of::<T>(), "not enough bytes ");
@@ -77,9 +166,138 @@ pub fn derefer<T>(bytes: &[u8]) -> *const T
 /// Get a mutable reference to a type from its bytes
 ///
 /// # Notes
-/// This function omits bounds checks in production builds
+/// The returned pointer is not guaranteed to be aligned for `T`. Use `read_unaligned()` and `write_unaligned()` on it.
 pub fn derefer_mut<T>(bytes: &mut [u8]) -> *mut T
 {
     assert!(bytes.len() >= mem::size_of::<T>(), "not enough bytes ");
     &mut bytes[0] as *mut u8 as *mut T
 }
+
+/// A working buffer of a size only known at runtime.
+///
+/// Kept on the stack if it is no larger than `N` bytes, otherwise allocated on the heap.
+#[cfg(feature="std")]
+pub enum Buffer<const N: usize>
+{
+    Stack([u8; N], usize),
+    Heap(Box<[u8]>),
+}
+
+#[cfg(feature="std")]
+impl<const N: usize> Buffer<N>
+{
+    /// Create a new zeroed buffer of `len` bytes
+    pub fn new(len: usize) -> Self
+    {
+	if len <= N {
+	    Self::Stack([0u8; N], len)
+	} else {
+	    Self::Heap(vec![0u8; len].into_boxed_slice())
+	}
+    }
+}
+
+#[cfg(feature="std")]
+impl<const N: usize> Deref for Buffer<N>
+{
+    type Target = [u8];
+    #[inline] fn deref(&self) -> &Self::Target
+    {
+	match self {
+	    Self::Stack(buffer, len) => &buffer[..*len],
+	    Self::Heap(buffer) => &buffer[..],
+	}
+    }
+}
+
+#[cfg(feature="std")]
+impl<const N: usize> DerefMut for Buffer<N>
+{
+    #[inline] fn deref_mut(&mut self) -> &mut Self::Target
+    {
+	match self {
+	    Self::Stack(buffer, len) => &mut buffer[..*len],
+	    Self::Heap(buffer) => &mut buffer[..],
+	}
+    }
+}
+
+pub(crate) mod sealed
+{
+    pub trait Sealed {}
+}
+
+/// Plain-old-data types that can be safely read from any `size_of::<T>()` bytes.
+///
+/// # Notes
+/// This trait is sealed and only implemented for types within this crate.
+pub trait Pod: sealed::Sealed + Copy {}
+
+/// Read a type from its bytes by copying them into an owned value.
+///
+/// Returns `None` if there are not enough bytes. Unlike `derefer()`, the alignment of `bytes` does not matter.
+pub fn read_struct<T: Pod>(bytes: &[u8]) -> Option<T>
+{
+    if bytes.len() < mem::size_of::<T>() {
+	return None;
+    }
+    let mut value = mem::MaybeUninit::<T>::uninit();
+    unsafe {
+	core::ptr::copy_nonoverlapping(bytes.as_ptr(), value.as_mut_ptr() as *mut u8, mem::size_of::<T>());
+	Some(value.assume_init())
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    #[test]
+    fn copy_slice_empty()
+    {
+	let mut dst = [0u8; 4];
+	assert_eq!(copy_slice(&mut dst[..], &[]), 0);
+	assert_eq!(copy_slice(&mut [], &[1u8, 2, 3]), 0);
+	assert_eq!(copy_slice(&mut [], &[]), 0);
+	assert_eq!(dst, [0u8; 4]);
+
+	assert_eq!(copy_slice(&mut dst[..], &[1u8, 2]), 2);
+	assert_eq!(dst, [1u8, 2, 0, 0]);
+    }
+
+    #[cfg(feature="std")]
+    #[test]
+    fn buffer_placement()
+    {
+	assert!(matches!(Buffer::<16>::new(16), Buffer::Stack(_, 16)));
+	assert!(matches!(Buffer::<16>::new(17), Buffer::Heap(_)));
+	assert_eq!(Buffer::<16>::new(3).len(), 3);
+	assert_eq!(Buffer::<16>::new(100).len(), 100);
+    }
+
+    #[cfg(feature="std")]
+    #[test]
+    fn hex()
+    {
+	assert_eq!(to_hex(&[0x00, 0x0f, 0xa5, 0xff]), "000fa5ff");
+	assert_eq!(from_hex("000fA5ff"), Some(vec![0x00, 0x0f, 0xa5, 0xff]));
+	assert_eq!(from_hex(""), Some(vec![]));
+	assert_eq!(from_hex("abc"), None);
+	assert_eq!(from_hex("0g"), None);
+	assert_eq!(from_hex("é0"), None);
+    }
+
+    #[test]
+    fn hex_into()
+    {
+	let mut output = [0u8; 4];
+	assert!(from_hex_into("000fA5ff", &mut output[..]));
+	assert_eq!(output, [0x00, 0x0f, 0xa5, 0xff]);
+	assert!(from_hex_into("", &mut []));
+
+	assert!(!from_hex_into("000fa5", &mut output[..]));
+	assert!(!from_hex_into("000fa5ff00", &mut output[..]));
+	assert!(!from_hex_into("000fa5fg", &mut output[..]));
+	assert!(!from_hex_into("000fa5é", &mut output[..]));
+    }
+}
@@ -0,0 +1,143 @@
+//! Algorithm-generic checksums
+//!
+//! Available when either the `sha256` or `checksum` feature is enabled, with an implementation for each.
+//! `MultiHasher` needs both.
+#[allow(unused_imports)]
+use super::*;
+#[cfg(all(feature="sha256", feature="checksum", feature="std"))]
+use std::io;
+
+/// An algorithm that can compute a checksum over a slice of bytes
+///
+/// This is implemented for the digest type of each algorithm, so code can be generic over which one it uses.
+///
+/// * `crc::Crc64` - CRC64 with the default polynomial
+/// * `sha256::Sha256Digest` - SHA256
+pub trait Checksum
+{
+    /// The checksum this algorithm produces
+    type Output;
+
+    /// Compute the checksum of a slice of bytes
+    fn checksum_slice(data: &[u8]) -> Self::Output;
+}
+
+#[cfg(feature="checksum")]
+impl Checksum for crc::Crc64
+{
+    type Output = u64;
+
+    #[inline] fn checksum_slice(data: &[u8]) -> Self::Output
+    {
+	crc::compute_slice(data)
+    }
+}
+
+#[cfg(feature="sha256")]
+impl Checksum for sha256::Sha256Digest
+{
+    type Output = sha256::Sha256Hash;
+
+    #[inline] fn checksum_slice(data: &[u8]) -> Self::Output
+    {
+	sha256::compute_slice(data)
+    }
+}
+
+/// A reader that computes both the SHA256 hash and the CRC64 checksum of all data read through it, in a single pass.
+///
+/// Implements `io::Read` with the `std` feature when the inner reader does.
+#[cfg(all(feature="sha256", feature="checksum"))]
+pub struct MultiHasher<R>
+{
+    inner: R,
+    sha256: sha256::Sha256Digest,
+    crc: crc::Crc64,
+}
+
+#[cfg(all(feature="sha256", feature="checksum"))]
+impl<R> MultiHasher<R>
+{
+    /// Wrap a reader
+    #[inline] pub fn new(inner: R) -> Self
+    {
+	Self {
+	    inner,
+	    sha256: Default::default(),
+	    crc: Default::default(),
+	}
+    }
+
+    /// The SHA256 hash and CRC64 checksum of all data read so far
+    #[inline] pub fn finish(self) -> (sha256::Sha256Hash, u64)
+    {
+	(self.sha256.finalize(), self.crc.finalize())
+    }
+
+    /// Consume into the inner reader, and the SHA256 hash and CRC64 checksum of all data read so far
+    #[inline] pub fn into_parts(self) -> (R, sha256::Sha256Hash, u64)
+    {
+	(self.inner, self.sha256.finalize(), self.crc.finalize())
+    }
+}
+
+#[cfg(all(feature="sha256", feature="checksum", feature="std"))]
+impl<R> io::Read for MultiHasher<R>
+where R: io::Read
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize>
+    {
+	let read = self.inner.read(buf)?;
+	self.sha256.update(&buf[..read]);
+	self.crc.update(&buf[..read]);
+	Ok(read)
+    }
+}
+
+#[cfg(all(feature="sha256", feature="checksum"))]
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    fn matches<C>(data: &[u8], expected: &C::Output) -> bool
+    where C: Checksum,
+	  C::Output: PartialEq
+    {
+	&C::checksum_slice(data) == expected
+    }
+
+    #[test]
+    fn generic()
+    {
+	let data = b"123456789";
+
+	assert!(matches::<crc::Crc64>(data, &0x995dc9bbdf1939fa));
+	assert!(!matches::<crc::Crc64>(&data[1..], &0x995dc9bbdf1939fa));
+	assert!(matches::<sha256::Sha256Digest>(data, &sha256::compute_slice(data)));
+	assert!(!matches::<sha256::Sha256Digest>(&data[1..], &sha256::compute_slice(data)));
+    }
+
+    #[cfg(feature="std")]
+    #[test]
+    fn multi_hasher()
+    {
+	use std::io::Read;
+	let data: Vec<u8> = (0..crate::consts::BUFFER_SIZE * 3 + 13).map(|x| x as u8).collect();
+
+	let mut reader = MultiHasher::new(&data[..]);
+	let mut output = Vec::new();
+	assert_eq!(reader.read_to_end(&mut output).unwrap(), data.len());
+	assert_eq!(output, data);
+	assert_eq!(reader.finish(), (sha256::compute_slice(&data[..]), crc::compute_slice(&data[..])));
+
+	let mut reader = MultiHasher::new(&data[..]);
+	let mut buffer = [0u8; 7];
+	assert_eq!(reader.read(&mut buffer[..]).unwrap(), 7);
+	let (rest, hash, crc) = reader.into_parts();
+	assert_eq!(rest, &data[7..]);
+	assert_eq!((hash, crc), (sha256::compute_slice(&data[..7]), crc::compute_slice(&data[..7])));
+
+	assert_eq!(MultiHasher::new(&b""[..]).finish(), (sha256::compute_slice([]), crc::compute_slice([])));
+    }
+}
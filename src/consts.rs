@@ -33,6 +33,9 @@ pub const RSA_SIG_SIZE: usize = 512;
 /// The number of bytes the RSA padding requires
 pub const RSA_PADDING_NEEDS: usize = 11;
 
+/// The number of bytes RSA OAEP padding requires (with openssl's default SHA1 digest)
+pub const RSA_OAEP_PADDING_NEEDS: usize = 42;
+
 /// The padding used for RSA operations
 #[cfg(feature="rsa")] 
 pub const RSA_PADDING: openssl::rsa::Padding = openssl::rsa::Padding::PKCS1;
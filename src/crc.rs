@@ -2,10 +2,15 @@
 
 use super::consts;
 #[allow(unused_imports)]
-use std::{
+use core::{
     marker::Unpin,
-    io,
+    hash::{
+	Hasher,
+	BuildHasher,
+    },
 };
+#[cfg(feature="std")]
+use std::io;
 use crc::{
     crc64,
     Hasher64,
@@ -14,42 +19,542 @@ use crc::{
 use tokio::io::{
     AsyncRead,
     AsyncReadExt,
+    AsyncBufRead,
+    AsyncBufReadExt,
+    ReadBuf,
 };
+#[cfg(feature="async")]
+use core::{
+    pin::Pin,
+    task::{
+	Context,
+	Poll,
+    },
+};
+#[allow(unused_imports)]
 use consts::BUFFER_SIZE;
 
+/// The polynomial used for CRC64 checksums
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Polynomial
+{
+    /// ECMA-182 polynomial. This is the default
+    Ecma,
+    /// ISO 3309 polynomial
+    Iso,
+}
+
+impl Polynomial
+{
+    /// The raw polynomial value
+    #[inline] pub const fn value(self) -> u64
+    {
+	match self {
+	    Self::Ecma => crc64::ECMA,
+	    Self::Iso => crc64::ISO,
+	}
+    }
+}
+
+impl Default for Polynomial
+{
+    #[inline]
+    fn default() -> Self
+    {
+	Self::Ecma
+    }
+}
+
+/// An incremental CRC64 digest.
+///
+/// Data can be fed in over any number of `update()` calls, the result is the same as computing the checksum over the concatenation of all of it.
+pub struct Crc64(crc64::Digest);
+
+impl Crc64
+{
+    /// Create a new empty digest
+    #[inline] pub fn new() -> Self
+    {
+	Self::with_polynomial(Default::default())
+    }
+
+    /// Create a new empty digest using a specific polynomial
+    #[inline] pub fn with_polynomial(poly: Polynomial) -> Self
+    {
+	Self(crc64::Digest::new(poly.value()))
+    }
+
+    /// Feed more data into this digest
+    #[inline] pub fn update(&mut self, data: &[u8])
+    {
+	Hasher64::write(&mut self.0, data);
+    }
+
+    /// Consume this digest into the checksum of all the data fed into it
+    #[inline] pub fn finalize(self) -> u64
+    {
+	self.0.sum64()
+    }
+}
+
+impl Default for Crc64
+{
+    #[inline]
+    fn default() -> Self
+    {
+	Self::new()
+    }
+}
+
+/// Lets a `Crc64` be used as a `core::hash::Hasher`, `finish()` is the checksum of everything written so far.
+///
+/// # Security
+/// CRC64 is **not** a cryptographic hash. It is fast and suitable for hash maps over trusted keys and for corruption checks, but it is trivial to produce collisions for, so do not use it with attacker controlled keys or for integrity against tampering.
+impl Hasher for Crc64
+{
+    #[inline] fn write(&mut self, bytes: &[u8])
+    {
+	self.update(bytes);
+    }
+
+    #[inline] fn finish(&self) -> u64
+    {
+	self.0.sum64()
+    }
+}
+
+/// A CRC64 `core::hash::Hasher`, this is the same type as `Crc64`.
+///
+/// # Security
+/// See the notes on the `Hasher` implementation of `Crc64`, this is non-cryptographic.
+pub type Crc64Hasher = Crc64;
+
+/// A `BuildHasher` creating `Crc64Hasher`s, for use with `HashMap` and `HashSet`.
+///
+/// # Security
+/// See the notes on the `Hasher` implementation of `Crc64`, this is non-cryptographic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Crc64BuildHasher(Polynomial);
+
+impl Crc64BuildHasher
+{
+    /// Create a new builder for hashers using this polynomial
+    #[inline] pub const fn new(poly: Polynomial) -> Self
+    {
+	Self(poly)
+    }
+}
+
+impl BuildHasher for Crc64BuildHasher
+{
+    type Hasher = Crc64;
+    #[inline] fn build_hasher(&self) -> Self::Hasher
+    {
+	Crc64::with_polynomial(self.0)
+    }
+}
+
+/// A reader that computes the CRC64 checksum of all data read through it.
+///
+/// Implements `io::Read` with the `std` feature, and `AsyncRead` with the `async` feature, when the inner reader does.
+pub struct Crc64Reader<R>
+{
+    inner: R,
+    digest: Crc64,
+}
+
+impl<R> Crc64Reader<R>
+{
+    /// Wrap a reader
+    #[inline] pub fn new(inner: R) -> Self
+    {
+	Self::with_polynomial(inner, Default::default())
+    }
+
+    /// Wrap a reader, using a specific polynomial
+    #[inline] pub fn with_polynomial(inner: R, poly: Polynomial) -> Self
+    {
+	Self {
+	    inner,
+	    digest: Crc64::with_polynomial(poly),
+	}
+    }
+
+    /// The checksum of all data read so far
+    #[inline] pub fn finish(self) -> u64
+    {
+	self.digest.finalize()
+    }
+
+    /// Consume into the inner reader and the checksum of all data read so far
+    #[inline] pub fn into_parts(self) -> (R, u64)
+    {
+	(self.inner, self.digest.finalize())
+    }
+}
+
+#[cfg(feature="std")]
+impl<R> io::Read for Crc64Reader<R>
+where R: io::Read
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize>
+    {
+	let read = self.inner.read(buf)?;
+	self.digest.update(&buf[..read]);
+	Ok(read)
+    }
+}
+
+#[cfg(feature="async")]
+impl<R> AsyncRead for Crc64Reader<R>
+where R: AsyncRead + Unpin
+{
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>>
+    {
+	let this = self.get_mut();
+	let start = buf.filled().len();
+	match Pin::new(&mut this.inner).poll_read(cx, buf) {
+	    Poll::Ready(Ok(())) => {
+		this.digest.update(&buf.filled()[start..]);
+		Poll::Ready(Ok(()))
+	    },
+	    other => other,
+	}
+    }
+}
+
 /// Compute a crc64 checksum from a slice.
-pub fn compute_slice(data: impl AsRef<[u8]>) -> u64
+#[inline] pub fn compute_slice(data: impl AsRef<[u8]>) -> u64
+{
+    compute_slice_with(data, Default::default())
+}
+
+/// Compute a crc64 checksum from a slice using a specific polynomial.
+pub fn compute_slice_with(data: impl AsRef<[u8]>, poly: Polynomial) -> u64
 {
-    let mut digest = crc64::Digest::new(crc64::ECMA);
-    digest.write(data.as_ref());
-    digest.sum64()
+    let mut digest = Crc64::with_polynomial(poly);
+    digest.update(data.as_ref());
+    digest.finalize()
+}
+
+/// Compute a crc64 checksum from a number of slices, as if they were one contiguous slice.
+#[inline] pub fn compute_slices<I, T>(from: I) -> u64
+where I: IntoIterator<Item=T>,
+      T: AsRef<[u8]>
+{
+    compute_slices_with(from, Default::default())
+}
+
+/// Compute a crc64 checksum from a number of slices using a specific polynomial, as if they were one contiguous slice.
+pub fn compute_slices_with<I, T>(from: I, poly: Polynomial) -> u64
+where I: IntoIterator<Item=T>,
+      T: AsRef<[u8]>
+{
+    let mut digest = Crc64::with_polynomial(poly);
+    for from in from.into_iter() {
+	digest.update(from.as_ref());
+    }
+    digest.finalize()
+}
+
+/// Combine the checksums of two pieces of data into the checksum of their concatenation.
+///
+/// `crc1` is the checksum of the first piece, `crc2` the checksum of the second, and `len2` the length in bytes of the second.
+/// This does not need the data itself, so pieces can be checksummed separately (e.g. in parallel) and combined afterwards.
+#[inline] pub fn combine(crc1: u64, crc2: u64, len2: usize) -> u64
+{
+    combine_with(crc1, crc2, len2, Default::default())
+}
+
+/// Combine the checksums of two pieces of data into the checksum of their concatenation using a specific polynomial.
+///
+/// # Notes
+/// Both checksums must have been computed with `poly`.
+pub fn combine_with(mut crc1: u64, crc2: u64, mut len2: usize, poly: Polynomial) -> u64
+{
+    /// Multiply the GF(2) matrix `mat` by the vector `vec`
+    fn times(mat: &[u64; 64], mut vec: u64) -> u64
+    {
+	let mut sum = 0;
+	let mut i = 0;
+	while vec != 0 {
+	    if vec & 1 != 0 {
+		sum ^= mat[i];
+	    }
+	    vec >>= 1;
+	    i += 1;
+	}
+	sum
+    }
+    /// Square the GF(2) matrix `mat` into `square`
+    fn square(square: &mut [u64; 64], mat: &[u64; 64])
+    {
+	for (square, &row) in square.iter_mut().zip(mat.iter()) {
+	    *square = times(mat, row);
+	}
+    }
+
+    if len2 == 0 {
+	return crc1;
+    }
+
+    // Operator for a single zero bit
+    let mut odd = [0u64; 64];
+    odd[0] = poly.value();
+    for (n, row) in odd.iter_mut().enumerate().skip(1) {
+	*row = 1 << (n - 1);
+    }
+    let mut even = [0u64; 64];
+
+    // Operators for two then four zero bits
+    square(&mut even, &odd);
+    square(&mut odd, &even);
+
+    // Apply `len2` zero bytes to `crc1`, squaring the operator for each bit of `len2`
+    loop {
+	square(&mut even, &odd);
+	if len2 & 1 != 0 {
+	    crc1 = times(&even, crc1);
+	}
+	len2 >>= 1;
+	if len2 == 0 {
+	    break;
+	}
+
+	square(&mut odd, &even);
+	if len2 & 1 != 0 {
+	    crc1 = times(&odd, crc1);
+	}
+	len2 >>= 1;
+	if len2 == 0 {
+	    break;
+	}
+    }
+
+    crc1 ^ crc2
 }
 
 /// Read a full stream into a CRC64 checksum
 #[cfg(feature="async")] 
-pub async fn compute_stream<T>(from: &mut T) -> io::Result<u64>
+#[inline] pub async fn compute_stream<T>(from: &mut T) -> io::Result<u64>
+    where T: AsyncRead + Unpin + ?Sized
+{
+    compute_stream_with(from, Default::default()).await
+}
+
+/// Read a full stream into a CRC64 checksum using a specific polynomial
+#[cfg(feature="async")] 
+pub async fn compute_stream_with<T>(from: &mut T, poly: Polynomial) -> io::Result<u64>
     where T: AsyncRead + Unpin + ?Sized
 {
     let mut buffer = [0u8; BUFFER_SIZE];
     let mut read;
-    let mut digest = crc64::Digest::new(crc64::ECMA);
+    let mut digest = Crc64::with_polynomial(poly);
     while (read = from.read(&mut buffer[..]).await?, read!=0).1
     {
-	digest.write(&buffer[..read]);
+	digest.update(&buffer[..read]);
     }
-    Ok(digest.sum64())
+    Ok(digest.finalize())
+}
+
+/// Read a full buffered stream into a CRC64 checksum
+///
+/// The stream's own buffer is checksummed directly, instead of being copied through another buffer like `compute_stream()` does.
+#[cfg(feature="async")] 
+#[inline] pub async fn compute_stream_buf<T>(from: &mut T) -> io::Result<u64>
+    where T: AsyncBufRead + Unpin + ?Sized
+{
+    compute_stream_with_buf(from, Default::default()).await
+}
+
+/// Read a full buffered stream into a CRC64 checksum using a specific polynomial
+///
+/// The stream's own buffer is checksummed directly, instead of being copied through another buffer like `compute_stream_with()` does.
+#[cfg(feature="async")] 
+pub async fn compute_stream_with_buf<T>(from: &mut T, poly: Polynomial) -> io::Result<u64>
+    where T: AsyncBufRead + Unpin + ?Sized
+{
+    let mut digest = Crc64::with_polynomial(poly);
+    loop {
+	let buffer = from.fill_buf().await?;
+	if buffer.is_empty() {
+	    break;
+	}
+	let read = buffer.len();
+	digest.update(buffer);
+	from.consume(read);
+    }
+    Ok(digest.finalize())
 }
 
 /// Read a full stream into a CRC64 checksum
-pub async fn compute_stream_sync<T>(from: &mut T) -> io::Result<u64>
-    where T: io::Read + Unpin + ?Sized
+#[cfg(feature="std")]
+#[inline] pub fn compute_stream_sync<T>(from: &mut T) -> io::Result<u64>
+    where T: io::Read + ?Sized
 {
-    let mut buffer = [0u8; BUFFER_SIZE];
+    compute_stream_sync_with(from, Default::default())
+}
+
+/// Read a full stream into a CRC64 checksum using a specific polynomial
+#[cfg(feature="std")]
+#[inline] pub fn compute_stream_sync_with<T>(from: &mut T, poly: Polynomial) -> io::Result<u64>
+    where T: io::Read + ?Sized
+{
+    compute_stream_sync_with_buffered(from, poly, BUFFER_SIZE)
+}
+
+/// Read a full stream into a CRC64 checksum, reading `buffer_size` bytes at a time
+#[cfg(feature="std")]
+#[inline] pub fn compute_stream_sync_buffered<T>(from: &mut T, buffer_size: usize) -> io::Result<u64>
+    where T: io::Read + ?Sized
+{
+    compute_stream_sync_with_buffered(from, Default::default(), buffer_size)
+}
+
+/// Read a full stream into a CRC64 checksum using a specific polynomial, reading `buffer_size` bytes at a time.
+///
+/// # Notes
+/// Buffers larger than `BUFFER_SIZE` are allocated on the heap.
+///
+/// # Panics
+/// If `buffer_size` is 0.
+#[cfg(feature="std")]
+pub fn compute_stream_sync_with_buffered<T>(from: &mut T, poly: Polynomial, buffer_size: usize) -> io::Result<u64>
+    where T: io::Read + ?Sized
+{
+    assert!(buffer_size > 0, "buffer size cannot be 0");
+    let mut buffer = crate::bytes::Buffer::<BUFFER_SIZE>::new(buffer_size);
     let mut read;
-    let mut digest = crc64::Digest::new(crc64::ECMA);
+    let mut digest = Crc64::with_polynomial(poly);
     while (read = from.read(&mut buffer[..])?, read!=0).1
     {
-	digest.write(&buffer[..read]);
+	digest.update(&buffer[..read]);
+    }
+    Ok(digest.finalize())
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    #[test]
+    fn incremental()
+    {
+	let data = b"hello world, this is some data to checksum";
+	let mut digest = Crc64::new();
+	for chunk in data.chunks(7) {
+	    digest.update(chunk);
+	}
+	assert_eq!(digest.finalize(), compute_slice(&data[..]));
+    }
+
+    #[test]
+    fn polynomials()
+    {
+	assert_eq!(compute_slice(b"123456789"), 0x995dc9bbdf1939fa);
+	assert_eq!(compute_slice_with(b"123456789", Polynomial::Ecma), 0x995dc9bbdf1939fa);
+	assert_eq!(compute_slice_with(b"123456789", Polynomial::Iso), 0xb90956c775a41001);
+    }
+
+    #[test]
+    fn slices()
+    {
+	let (a, b) = (&b"hello "[..], &b"world"[..]);
+	assert_eq!(compute_slices([a, b]), compute_slice([a, b].concat()));
+	assert_eq!(compute_slices_with([a, b], Polynomial::Iso), compute_slice_with([a, b].concat(), Polynomial::Iso));
+	assert_eq!(compute_slices(b"123456789".chunks(2)), 0x995dc9bbdf1939fa);
+	assert_eq!(compute_slices(std::iter::empty::<&[u8]>()), compute_slice([]));
+    }
+
+    #[test]
+    fn hasher()
+    {
+	let data = b"some bytes to hash";
+	let mut hasher = Crc64::new();
+	hasher.write(&data[..]);
+	assert_eq!(hasher.finish(), compute_slice(&data[..]));
+	hasher.update(&data[..]);
+	assert_eq!(hasher.finish(), compute_slice(&[&data[..], &data[..]].concat()[..]));
+
+	let mut map = std::collections::HashMap::with_hasher(Crc64BuildHasher::default());
+	map.insert("key", 1);
+	assert_eq!(map.get("key"), Some(&1));
+    }
+
+    #[cfg(feature="std")]
+    #[test]
+    fn reader()
+    {
+	let data = vec![0xa5u8; BUFFER_SIZE * 3 + 17];
+	let mut reader = Crc64Reader::new(&data[..]);
+	let mut output = Vec::new();
+	io::copy(&mut reader, &mut output).unwrap();
+
+	assert_eq!(output, data);
+	assert_eq!(reader.finish(), compute_slice(&data[..]));
+    }
+
+    #[cfg(feature="std")]
+    #[test]
+    fn stream_sync()
+    {
+	let data = b"synchronous stream data";
+	assert_eq!(compute_stream_sync(&mut &data[..]).unwrap(), compute_slice(&data[..]));
+	assert_eq!(compute_stream_sync_with(&mut &data[..], Polynomial::Iso).unwrap(), compute_slice_with(&data[..], Polynomial::Iso));
+    }
+
+    #[cfg(feature="std")]
+    #[test]
+    fn stream_sync_not_unpin()
+    {
+	struct NotUnpin<'a>(&'a [u8], std::marker::PhantomPinned);
+	impl io::Read for NotUnpin<'_>
+	{
+	    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize>
+	    {
+		io::Read::read(&mut self.0, buf)
+	    }
+	}
+	let data = b"reader that is not Unpin";
+	let mut reader = NotUnpin(&data[..], std::marker::PhantomPinned);
+	assert_eq!(compute_stream_sync(&mut reader).unwrap(), compute_slice(&data[..]));
+    }
+
+    #[cfg(feature="std")]
+    #[test]
+    fn stream_buffered()
+    {
+	let data = vec![0xa5u8; BUFFER_SIZE * 20 + 17];
+	let checksum = compute_stream_sync(&mut &data[..]).unwrap();
+
+	assert_eq!(compute_stream_sync_buffered(&mut &data[..], 65536).unwrap(), checksum);
+	assert_eq!(compute_stream_sync_buffered(&mut &data[..], 7).unwrap(), checksum);
+	assert_eq!(checksum, compute_slice(&data[..]));
+    }
+
+    #[test]
+    fn combine_checksums()
+    {
+	let data: Vec<u8> = (0..BUFFER_SIZE * 3 + 17).map(|x| x as u8).collect();
+	for &split in [0, 1, 9, 100, BUFFER_SIZE, data.len()].iter() {
+	    let (a, b) = data.split_at(split);
+	    assert_eq!(combine(compute_slice(a), compute_slice(b), b.len()), compute_slice(&data[..]));
+	    assert_eq!(combine_with(compute_slice_with(a, Polynomial::Iso), compute_slice_with(b, Polynomial::Iso), b.len(), Polynomial::Iso),
+		       compute_slice_with(&data[..], Polynomial::Iso));
+	}
+	assert_eq!(combine(compute_slice(b"12345"), compute_slice(b"6789"), 4), 0x995dc9bbdf1939fa);
+    }
+
+    #[cfg(feature="async")]
+    #[tokio::test]
+    async fn stream_buf()
+    {
+	let data: Vec<u8> = (0..BUFFER_SIZE * 3 + 17).map(|x| x as u8).collect();
+	let checksum = compute_stream(&mut &data[..]).await.unwrap();
+
+	assert_eq!(compute_stream_buf(&mut tokio::io::BufReader::new(&data[..])).await.unwrap(), checksum);
+	assert_eq!(compute_stream_buf(&mut tokio::io::BufReader::with_capacity(7, &data[..])).await.unwrap(), checksum);
+	assert_eq!(compute_stream_with_buf(&mut tokio::io::BufReader::new(&data[..]), Polynomial::Iso).await.unwrap(),
+		   compute_stream_with(&mut &data[..], Polynomial::Iso).await.unwrap());
     }
-    Ok(digest.sum64())
 }
@@ -10,15 +10,23 @@ use openssl::{
 };
 
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum Error
 {
     Encrypt,
+    /// The ciphertext could not be decrypted, because the key is wrong or the ciphertext has been corrupted
     Decrypt,
+    /// The ciphertext ended part way through a block
+    Truncated,
+    /// The cipher cannot be used here, e.g. an AEAD cipher whose tag would be lost
+    Cipher,
     Internal(ErrorStack),
     IO(io::Error),
     Random,
 
     Length{expected: Option<usize>, got: Option<usize>},
+    /// Any other error, with its own message
+    Other(Box<dyn error::Error + Send + Sync>),
     
     Unknown,
 }
@@ -30,6 +38,7 @@ impl error::Error for Error
 	match &self {
 	    Error::Internal(stack) => Some(stack),
 	    Error::IO(io) => Some(io),
+	    Error::Other(other) => Some(&**other),
 	    _ => None,
 	}
     }
@@ -45,10 +54,13 @@ impl fmt::Display for Error
 	    Error::IO(io) => write!(f, "io: {}", io),
 	    Error::Encrypt => write!(f, "encryption failed"),
 	    Error::Decrypt => write!(f, "decryption failed"),
+	    Error::Truncated => write!(f, "ciphertext is truncated"),
+	    Error::Cipher => write!(f, "unsupported cipher"),
 	    Error::Internal(ssl) => write!(f, "internal: {}", ssl),
 	    Error::Length{expected: Some(expected), got: Some(got)} => write!(f, "bad length: expected {}, got {}", expected, got),
 	    Error::Length{expected: Some(expected), ..} => write!(f, "bad length: expected {}", expected),
 	    Error::Length{got: Some(got), ..} => write!(f, "bad length: got {}", got),
+	    Error::Other(other) => write!(f, "{}", other),
 	    _ => write!(f, "unknown"),
 	}
     }
@@ -77,3 +89,11 @@ impl From<io::Error> for Error
 	Self::IO(i)
     }
 }
+
+impl From<Box<dyn error::Error + Send + Sync>> for Error
+{
+    #[inline] fn from(from: Box<dyn error::Error + Send + Sync>) -> Self
+    {
+	Self::Other(from)
+    }
+}
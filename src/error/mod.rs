@@ -9,3 +9,141 @@ pub mod password;
 pub mod aes;
 #[cfg(feature="rsa")]
 pub mod rsa;
+
+/// An error from any part of this crate.
+///
+/// Each enabled module's error converts into this with `?`. The module errors are still available for more granular handling.
+/// It only exists when at least one module with its own error is enabled.
+#[cfg(any(feature="password", feature="aes", feature="rsa"))]
+#[derive(Debug)]
+pub enum Error
+{
+    #[cfg(feature="password")] 
+    Password(password::Error),
+    #[cfg(feature="aes")]
+    Aes(aes::Error),
+    #[cfg(feature="rsa")]
+    Rsa(rsa::Error),
+}
+
+#[cfg(any(feature="password", feature="aes", feature="rsa"))]
+impl error::Error for Error
+{
+    fn source(&self) -> Option<&(dyn error::Error + 'static)>
+    {
+	match *self {
+	    #[cfg(feature="password")] 
+	    Self::Password(ref err) => err.source(),
+	    #[cfg(feature="aes")]
+	    Self::Aes(ref err) => err.source(),
+	    #[cfg(feature="rsa")]
+	    Self::Rsa(ref err) => err.source(),
+	}
+    }
+}
+
+#[cfg(any(feature="password", feature="aes", feature="rsa"))]
+impl fmt::Display for Error
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+	match *self {
+	    #[cfg(feature="password")] 
+	    Self::Password(ref err) => fmt::Display::fmt(err, f),
+	    #[cfg(feature="aes")]
+	    Self::Aes(ref err) => fmt::Display::fmt(err, f),
+	    #[cfg(feature="rsa")]
+	    Self::Rsa(ref err) => fmt::Display::fmt(err, f),
+	}
+    }
+}
+
+#[cfg(feature="password")] 
+impl From<password::Error> for Error
+{
+    #[inline] fn from(from: password::Error) -> Self
+    {
+	Self::Password(from)
+    }
+}
+
+#[cfg(feature="aes")]
+impl From<aes::Error> for Error
+{
+    #[inline] fn from(from: aes::Error) -> Self
+    {
+	Self::Aes(from)
+    }
+}
+
+#[cfg(feature="rsa")]
+impl From<rsa::Error> for Error
+{
+    #[inline] fn from(from: rsa::Error) -> Self
+    {
+	Self::Rsa(from)
+    }
+}
+
+#[cfg(all(test, feature="aes", feature="rsa"))]
+mod tests
+{
+    use super::*;
+    #[test]
+    fn propagate()
+    {
+	fn aes() -> Result<(), Error>
+	{
+	    crate::aes::AesKey::from_slice([0u8; 1], [0u8; 1])?;
+	    Ok(())
+	}
+	fn rsa() -> Result<(), Error>
+	{
+	    crate::rsa::RsaPublicKey::from_pem("not a pem")?;
+	    Ok(())
+	}
+
+	assert!(matches!(aes(), Err(Error::Aes(aes::Error::Length{..}))));
+	assert!(matches!(rsa(), Err(Error::Rsa(_))));
+	assert!(error::Error::source(&rsa().unwrap_err()).is_some());
+    }
+
+    #[test]
+    fn sources()
+    {
+	use std::io;
+	use error::Error as _;
+	fn io() -> io::Error
+	{
+	    io::Error::other("io")
+	}
+	let utf8 = std::str::from_utf8(&[0xff, 0xfe][..]).unwrap_err();
+	let ssl = openssl::rsa::Rsa::public_key_from_pem(b"not a pem").unwrap_err();
+
+	assert!(aes::Error::from(io()).source().is_some());
+	assert!(aes::Error::from(ssl.clone()).source().is_some());
+	assert!(rsa::Error::from(io()).source().is_some());
+	assert!(rsa::Error::from(ssl).source().is_some());
+	assert!(rsa::Error::from(utf8).source().is_some());
+    }
+
+    #[test]
+    fn other()
+    {
+	use error::Error as _;
+	fn other() -> Box<dyn error::Error + Send + Sync>
+	{
+	    "backend failed".into()
+	}
+
+	assert_eq!(aes::Error::from(other()).to_string(), "aes error: backend failed");
+	assert_eq!(password::Error::from(other()).to_string(), "backend failed");
+	assert_eq!(rsa::Error::from(other()).to_string(), "backend failed");
+	assert_eq!(Error::from(rsa::Error::Other(other())).to_string(), "backend failed");
+
+	assert!(matches!(rsa::Error::from(other()), rsa::Error::Other(_)));
+	assert_eq!(aes::Error::from(other()).source().unwrap().to_string(), "backend failed");
+	assert!(password::Error::from(other()).source().is_some());
+	assert!(rsa::Error::from(other()).source().is_some());
+    }
+}
@@ -3,13 +3,34 @@ use super::*;
 
 /// Represents an error regarding password related operations
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum Error
 {
+    /// The RNG passed to a `_with_rng()` function failed
     Random,
+    /// The OS's random number source failed
+    RandomSource(getrandom::Error),
     Unknown,
     Length{expected: Option<usize>, got: Option<usize>},
+    Phc,
+    Params,
+    Encoding,
+    /// The hash was derived with a key derivation function that does not support the operation
+    Kdf,
+    /// Any other error, with its own message
+    Other(Box<dyn error::Error + Send + Sync>),
+}
+impl error::Error for Error
+{
+    fn source(&self) -> Option<&(dyn error::Error + 'static)>
+    {
+	match self {
+	    Self::RandomSource(rng) => Some(rng),
+	    Self::Other(other) => Some(&**other),
+	    _ => None,
+	}
+    }
 }
-impl error::Error for Error{}
 
 impl fmt::Display for Error
 {
@@ -17,11 +38,33 @@ impl fmt::Display for Error
     {
 	match self {
 	    Error::Random => write!(f, "rng failure"),
+	    Error::RandomSource(rng) => write!(f, "rng failure: {}", rng),
 	    Error::Length{expected: Some(expected), got: Some(got)} => write!(f, "bad length: expected {}, got {}", expected, got),
 	    Error::Length{expected: Some(expected), ..} => write!(f, "bad length: expected {}", expected),
 	    Error::Length{got: Some(got), ..} => write!(f, "bad length: got {}", got),
 	    Error::Length{..} => write!(f, "bad length"),
+	    Error::Phc => write!(f, "invalid PHC string"),
+	    Error::Params => write!(f, "invalid key derivation parameters"),
+	    Error::Encoding => write!(f, "invalid hex or base64 string"),
+	    Error::Kdf => write!(f, "unsupported key derivation function"),
+	    Error::Other(other) => write!(f, "{}", other),
 	    _ => write!(f, "unknown"),	    
 	}
     }
 }
+
+impl From<getrandom::Error> for Error
+{
+    #[inline] fn from(from: getrandom::Error) -> Self
+    {
+	Self::RandomSource(from)
+    }
+}
+
+impl From<Box<dyn error::Error + Send + Sync>> for Error
+{
+    #[inline] fn from(from: Box<dyn error::Error + Send + Sync>) -> Self
+    {
+	Self::Other(from)
+    }
+}
@@ -4,6 +4,7 @@ use std::{
     io,
     error,
     num::TryFromIntError,
+    str::Utf8Error,
 };
 use openssl::{
     error::ErrorStack,
@@ -19,18 +20,29 @@ pub enum BinaryErrorKind {
 
 /// Represents an error for RSA operations
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum Error {
     Encrypt,
     Decrypt,
+    /// A signature was not valid for the data
+    Verify,
     
     Integer,
     Key,
     Password,
     PEM,
+    /// The PEM block at this index of a bundle was invalid
+    PEMBlock(usize),
+    /// The JSON web key was not an RSA key, or was missing or had an invalid component
+    JWK,
     Binary(BinaryErrorKind),
-    Utf8,
+    /// The input is larger than the key can encrypt in one block
+    TooLarge{max: usize, got: usize},
+    Utf8(Utf8Error),
     OpenSSLInternal(ErrorStack),
     IO(io::Error),
+    /// Any other error, with its own message
+    Other(Box<dyn error::Error + Send + Sync>),
     Unknown,
 }
 
@@ -41,6 +53,8 @@ impl error::Error for Error
 	Some(match &self {
 	    Self::IO(io) => io,
 	    Self::OpenSSLInternal(ssl) => ssl,
+	    Self::Utf8(utf8) => utf8,
+	    Self::Other(other) => &**other,
 	    _ => return None,
 	})
     }
@@ -52,19 +66,24 @@ impl std::fmt::Display for Error
 	match self {
 	    Self::Encrypt => write!(f, "encryption failed"),
 	    Self::Decrypt => write!(f, "decryption failed"),
+	    Self::Verify => write!(f, "signature verification failed"),
 	    Self::Integer => write!(f, "integer operation exceeded bounds (overflow/underflow)"),
 	    Self::Key => write!(f, "invalid key"),
-	    Self::Password => write!(f, "a password is needed but none was provided"),
+	    Self::Password => write!(f, "a password is needed but none was provided (use `RsaPrivateKey::from_pem()` for encrypted keys)"),
 	    Self::PEM => write!(f, "invalid PEM string"),
+	    Self::PEMBlock(index) => write!(f, "invalid PEM string: bad block at index {}", index),
+	    Self::JWK => write!(f, "invalid JSON web key"),
 	    Self::Binary(BinaryErrorKind::Length{expected: Some(expected), got: Some(got)}) => write!(f, "invalid binary representation: bad length (expected {} got {})", expected, got),
 	    Self::Binary(BinaryErrorKind::Length{expected: Some(expected), ..}) => write!(f, "invalid binary representation: bad length (expected {})", expected),
 	    Self::Binary(BinaryErrorKind::Length{got: Some(got), ..}) => write!(f, "invalid binary representation: bad length (got {})", got),
 	    Self::Binary(BinaryErrorKind::Length{..}) => write!(f, "invalid binary representation: bad length"),
 	    Self::Binary(BinaryErrorKind::Corruption) => write!(f, "invalid binary representation: corrupted data"),
 	    Self::Binary(_) => write!(f, "invalid binary representation"),
-	    Self::Utf8 => write!(f, "text contained invalid utf8"),
+	    Self::TooLarge{max, got} => write!(f, "input too large to encrypt with this key: maximum {} bytes, got {}", max, got),
+	    Self::Utf8(_) => write!(f, "text contained invalid utf8"),
 	    Self::IO(io) => write!(f, "i/o error: {}", io),
 	    Self::OpenSSLInternal(ssl) => write!(f, "openssl error: {}", ssl),
+	    Self::Other(other) => write!(f, "{}", other),
 	    _ => write!(f, "unknown error"),
 	}
     }
@@ -86,11 +105,19 @@ impl From<io::Error> for Error
     }
 }
 
-impl From<std::str::Utf8Error> for Error
+impl From<Utf8Error> for Error
 {
-    fn from(_: std::str::Utf8Error) -> Self
+    fn from(from: Utf8Error) -> Self
     {
-	Self::Utf8
+	Self::Utf8(from)
+    }
+}
+
+impl From<Box<dyn error::Error + Send + Sync>> for Error
+{
+    #[inline] fn from(from: Box<dyn error::Error + Send + Sync>) -> Self
+    {
+	Self::Other(from)
     }
 }
 
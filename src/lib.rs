@@ -6,23 +6,43 @@
 //! * aes - `aes` feature
 //! * crc - `checksum` feature
 //! * rsa - `rsa` feature
+//! * checksum - the `Checksum` trait, with either `sha256` or `checksum`
 //!
 //! There is also `full` for enabling them all.
 //!
+//! # Optional extras
+//! These are not included in `full`.
+//!
+//! * argon2 - Argon2id password hashing in `password`
+//! * scrypt - scrypt password hashing in `password`
+//! * parallel - multi-threaded tree hashing in `sha256`
+//! * jwk - JSON Web Key import and export in `rsa`
+//! * x509 - reading public keys from X.509 certificates in `rsa`
+//! * fs - hashing whole directories in `sha256`
+//!
 //! # Async processing
 //! The `async` feature adds asynchronous streaming functions with Tokio's `AsyncRead` and `AsyncWrite` traits.
+//!
+//! # `no_std`
+//! The `std` feature is on by default. Without it the crate is `no_std`, and only the slice hashing functions in `sha256` and `crc` are available.
+//! Every other module, and the `std::io` stream functions, need `std`.
 
+#![cfg_attr(not(any(feature="std", test)), no_std)]
 #![allow(dead_code)]
 
 pub mod consts;
 #[allow(unused_imports)]
 use consts::*;
 
+#[cfg(feature="std")]
 mod util;
 mod bytes;
 
+#[cfg(feature="std")]
 #[allow(unused_imports)]
 mod error;
+#[cfg(all(feature="std", any(feature="password", feature="aes", feature="rsa")))]
+pub use error::Error;
 
 #[cfg(feature="serialise")]
 use serde_derive::{
@@ -33,6 +53,10 @@ use serde_derive::{
 
 #[cfg(feature="sha256")]
 pub use sha2;
+#[cfg(any(feature="aes", feature="password"))]
+pub use rand_core;
+#[cfg(any(feature="aes", feature="password"))]
+pub use zeroize;
 #[cfg(feature="sha256")] 
 pub mod sha256;
 
@@ -43,5 +67,7 @@ pub mod password;
 pub mod aes;
 #[cfg(feature="checksum")]
 pub mod crc;
+#[cfg(any(feature="sha256", feature="checksum"))]
+pub mod checksum;
 #[cfg(feature="rsa")]
 pub mod rsa;
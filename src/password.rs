@@ -2,6 +2,11 @@
 use super::*;
 use std::{
     fmt,
+    convert::TryFrom,
+    time::{
+	Duration,
+	Instant,
+    },
 };
 use pbkdf2::{
     pbkdf2,
@@ -12,17 +17,95 @@ use sha2::{
 use hex_literal::hex;
 use hmac::Hmac;
 use getrandom::getrandom;
+use rand_core::RngCore;
+use zeroize::Zeroizing;
 
 pub const SALTSIZE: usize = consts::PASSWORD_SALTSIZE;
 pub const KEYSIZE: usize = consts::PASSWORD_KEYSIZE;
 pub const ROUNDS: u32 = consts::PASSWORD_ROUNDS;
 
 /// Represents a password hash
-#[derive(Clone, Debug, PartialEq, Eq, Hash, Default)]
-#[repr(transparent)]
+///
+/// The hash records the number of rounds it was derived with, so validation does not depend on the current value of `ROUNDS`.
+/// When serialised the rounds are stored alongside the hash. Data serialised before the rounds were stored is assumed to have been derived with `ROUNDS`.
+/// The hash also records the key derivation function it was derived with, see `Kdf`.
+#[derive(Clone, PartialEq, Eq, Hash)]
 #[cfg_attr(feature="serialise", derive(Serialize,Deserialize))]
 pub struct Password {
     derived: [u8; KEYSIZE],
+    #[cfg_attr(feature="serialise", serde(default="default_rounds"))]
+    rounds: u32,
+    #[cfg_attr(feature="serialise", serde(default))]
+    kdf: Kdf,
+}
+
+/// The key derivation function a `Password` hash was derived with
+///
+/// Only `Pbkdf2` hashes can be used with `validate()`, `needs_rehash()` and `to_phc()`, hashes derived with another function must be validated with that function's own `validate_*()` method.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature="serialise", derive(Serialize,Deserialize))]
+#[non_exhaustive]
+pub enum Kdf
+{
+    /// PBKDF2 with HMAC-SHA256
+    Pbkdf2,
+    /// Argon2id
+    #[cfg(feature="argon2")]
+    Argon2,
+    /// scrypt
+    #[cfg(feature="scrypt")]
+    Scrypt,
+}
+
+impl Kdf
+{
+    /// The identifier used for this function in `StoredPassword::to_bytes()`
+    const fn tag(self) -> u8
+    {
+	match self {
+	    Self::Pbkdf2 => 0,
+	    #[cfg(feature="argon2")]
+	    Self::Argon2 => 1,
+	    #[cfg(feature="scrypt")]
+	    Self::Scrypt => 2,
+	}
+    }
+
+    fn from_tag(tag: u8) -> Option<Self>
+    {
+	match tag {
+	    0 => Some(Self::Pbkdf2),
+	    #[cfg(feature="argon2")]
+	    1 => Some(Self::Argon2),
+	    #[cfg(feature="scrypt")]
+	    2 => Some(Self::Scrypt),
+	    _ => None,
+	}
+    }
+}
+
+impl Default for Kdf
+{
+    #[inline]
+    fn default() -> Self
+    {
+	Self::Pbkdf2
+    }
+}
+
+#[cfg(feature="serialise")]
+#[inline] const fn default_rounds() -> u32
+{
+    ROUNDS
+}
+
+impl Default for Password
+{
+    #[inline]
+    fn default() -> Self
+    {
+	Self::empty()
+    }
 }
 
 /// Represents a salt to be used for password operations
@@ -55,7 +138,18 @@ impl Salt
     pub fn random() -> Result<Self, Error>
     {
 	let mut output = [0u8; SALTSIZE];
-	match getrandom(&mut output[..]) {
+	getrandom(&mut output[..])?;
+	Ok(Self(output))
+    }
+
+    /// Generate a salt from a specific RNG.
+    ///
+    /// # Notes
+    /// `random()` uses the OS's RNG, this is for when the output must be reproducible (e.g. tests with a seeded RNG).
+    pub fn random_with_rng(mut rng: impl RngCore) -> Result<Self, Error>
+    {
+	let mut output = [0u8; SALTSIZE];
+	match rng.try_fill_bytes(&mut output[..]) {
 	    Ok(_) => Ok(Self(output)),
 	    Err(_) => Err(Error::Random),
 	}
@@ -85,6 +179,60 @@ impl Salt
 	Self([0u8; SALTSIZE])
     }
 
+    /// Combine this salt with another by XORing their bytes
+    pub fn xor(&self, other: &Salt) -> Salt
+    {
+	let mut output = self.0;
+	for (output, other) in output.iter_mut().zip(other.0.iter()) {
+	    *output ^= other;
+	}
+	Self(output)
+    }
+
+    /// Compare this salt with another in constant time
+    #[inline] pub fn ct_eq(&self, other: &Salt) -> bool
+    {
+	bytes::ct_eq(&self.0[..], &other.0[..])
+    }
+
+    /// Encode this salt as a lowercase hex string
+    #[inline] pub fn to_hex(&self) -> String
+    {
+	bytes::to_hex(&self.0[..])
+    }
+
+    /// Decode a salt from a hex string.
+    ///
+    /// Fails if the string is not valid hex, or does not decode to exactly `SALTSIZE` bytes.
+    pub fn from_hex(from: impl AsRef<str>) -> Result<Self, Error>
+    {
+	let bytes = bytes::from_hex(from.as_ref()).ok_or(Error::Encoding)?;
+	Self::exact(&bytes[..])
+    }
+
+    /// Encode this salt as a base64 string
+    #[inline] pub fn to_base64(&self) -> String
+    {
+	base64::encode(&self.0[..])
+    }
+
+    /// Decode a salt from a base64 string.
+    ///
+    /// Fails if the string is not valid base64, or does not decode to exactly `SALTSIZE` bytes.
+    pub fn from_base64(from: impl AsRef<str>) -> Result<Self, Error>
+    {
+	let bytes = base64::decode(from.as_ref()).map_err(|_| Error::Encoding)?;
+	Self::exact(&bytes[..])
+    }
+
+    fn exact(from: &[u8]) -> Result<Self, Error>
+    {
+	if from.len() != SALTSIZE {
+	    return Err(Error::Length{expected: Some(SALTSIZE), got: Some(from.len())});
+	}
+	Self::slice(from)
+    }
+
 }
 
 impl From<[u8; SALTSIZE]> for Salt
@@ -95,6 +243,19 @@ impl From<[u8; SALTSIZE]> for Salt
     }
 }
 
+const _: () = assert!(SALTSIZE == sha256::SIZE, "a SHA256 hash is not the size of a salt");
+
+/// Use the bytes of a hash directly as a salt, e.g. to derive a salt from an identifier.
+impl From<sha256::Sha256Hash> for Salt
+{
+    fn from(from: sha256::Sha256Hash) -> Self
+    {
+	let mut output = [0u8; SALTSIZE];
+	bytes::copy_slice(&mut output[..], from.as_ref());
+	Self(output)
+    }
+}
+
 impl From<Salt> for [u8; SALTSIZE]
 {
     #[inline] fn from(from: Salt) -> Self
@@ -119,13 +280,130 @@ impl AsMut<[u8]> for Salt
     }
 }
 
+/// A salt of any length.
+///
+/// Prefer `Salt` unless interoperating with a system that uses salts that are not `SALTSIZE` bytes long.
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Default)]
+#[cfg_attr(feature="serialise", derive(Serialize,Deserialize))]
+pub struct SaltVec(Vec<u8>);
+
+impl SaltVec
+{
+    /// Generate a random salt of `len` bytes
+    pub fn random_len(len: usize) -> Result<Self, Error>
+    {
+	let mut output = vec![0u8; len];
+	getrandom(&mut output[..])?;
+	Ok(Self(output))
+    }
+
+    /// Generate a salt of `len` bytes from a specific RNG.
+    ///
+    /// # Notes
+    /// `random_len()` uses the OS's RNG, this is for when the output must be reproducible (e.g. tests with a seeded RNG).
+    pub fn random_len_with_rng(len: usize, mut rng: impl RngCore) -> Result<Self, Error>
+    {
+	let mut output = vec![0u8; len];
+	match rng.try_fill_bytes(&mut output[..]) {
+	    Ok(_) => Ok(Self(output)),
+	    Err(_) => Err(Error::Random),
+	}
+    }
+
+    /// Create a specific salt
+    #[inline] pub fn specific(from: impl Into<Vec<u8>>) -> Self
+    {
+	Self(from.into())
+    }
+
+    /// The length of this salt in bytes
+    #[inline] pub fn len(&self) -> usize
+    {
+	self.0.len()
+    }
+
+    /// Is this salt empty
+    #[inline] pub fn is_empty(&self) -> bool
+    {
+	self.0.is_empty()
+    }
+}
+
+impl From<Vec<u8>> for SaltVec
+{
+    #[inline] fn from(from: Vec<u8>) -> Self
+    {
+	Self(from)
+    }
+}
+
+impl From<SaltVec> for Vec<u8>
+{
+    #[inline] fn from(from: SaltVec) -> Self
+    {
+	from.0
+    }
+}
+
+impl From<Salt> for SaltVec
+{
+    #[inline] fn from(from: Salt) -> Self
+    {
+	Self(Vec::from(&from.0[..]))
+    }
+}
+
+impl AsRef<[u8]> for SaltVec
+{
+    #[inline] fn as_ref(&self) -> &[u8]
+    {
+	&self.0[..]
+    }
+}
+
+impl AsMut<[u8]> for SaltVec
+{
+    #[inline] fn as_mut(&mut self) -> &mut [u8]
+    {
+	&mut self.0[..]
+    }
+}
 
 impl Password
 {
-    /// Create from a specific hash
+    /// Create from a specific hash, assuming it was derived with the default number of rounds
     #[inline] pub const fn from_bytes(derived: [u8; KEYSIZE]) -> Self
     {
-	Self { derived }
+	Self::from_bytes_with_rounds(derived, ROUNDS)
+    }
+
+    /// Create from a specific hash that was derived with `rounds` rounds
+    #[inline] pub const fn from_bytes_with_rounds(derived: [u8; KEYSIZE], rounds: u32) -> Self
+    {
+	Self { derived, rounds, kdf: Kdf::Pbkdf2 }
+    }
+
+    /// The number of PBKDF2 rounds this hash was derived with
+    ///
+    /// This is `0` for hashes that were not derived with PBKDF2.
+    #[inline] pub const fn rounds(&self) -> u32
+    {
+	self.rounds
+    }
+
+    /// The key derivation function this hash was derived with
+    #[inline] pub const fn kdf(&self) -> Kdf
+    {
+	self.kdf
+    }
+
+    /// Was this hash derived with fewer rounds than `target_rounds`.
+    ///
+    /// After successfully validating a password, this can be used to decide whether to re-derive and store the hash with the current number of rounds.
+    /// Hashes not derived with PBKDF2 have no rounds to compare, so this is always `false` for them.
+    #[inline] pub const fn needs_rehash(&self, target_rounds: u32) -> bool
+    {
+	matches!(self.kdf, Kdf::Pbkdf2) && self.rounds < target_rounds
     }
 
     /// Consume into the hash bytes
@@ -134,36 +412,470 @@ impl Password
     {
 	self.derived
     }
+
+    /// Consume into the hash bytes, which are zeroed when dropped
+    #[inline]
+    pub fn into_bytes_zeroizing(self) -> Zeroizing<[u8; KEYSIZE]>
+    {
+	Zeroizing::new(self.derived)
+    }
     
     /// Create an empty password hash container
     #[inline(always)] pub const fn empty() -> Self
     {
-	Self{derived: [0u8; KEYSIZE]}
+	Self{derived: [0u8; KEYSIZE], rounds: ROUNDS, kdf: Kdf::Pbkdf2}
     }
     
     /// Create an AES key from this password hash
+    ///
+    /// The first `AES_KEYSIZE` bytes of the hash are used as the key, and the next `AES_IVSIZE` bytes as the IV.
+    ///
+    /// # Notes
+    /// This depends on `PASSWORD_KEYSIZE` being at least `AES_KEYSIZE + AES_IVSIZE`, which is checked at compile time, so it does not currently fail.
+    /// The `Result` is kept so that the constants can change without changing the signature again.
     #[cfg(feature="aes")] 
-    #[inline] pub fn create_aes(&self) -> aes::AesKey
+    #[inline] pub fn create_aes(&self) -> Result<aes::AesKey, aes::Error>
+    {
+	Ok(self.aes_key())
+    }
+
+    /// Create an AES key from this password hash
+    ///
+    /// # Deprecated
+    /// Use `create_aes()` instead.
+    #[cfg(feature="aes")]
+    #[deprecated(note = "use `create_aes()`, which returns a `Result` instead of relying on the key size constants")] #[inline] pub fn create_aes_key(&self) -> aes::AesKey
+    {
+	self.aes_key()
+    }
+
+    #[cfg(feature="aes")]
+    fn aes_key(&self) -> aes::AesKey
     {
-	aes::AesKey::from_slice(&self.derived[..consts::AES_KEYSIZE], &self.derived[consts::AES_KEYSIZE..]).unwrap()
+	let mut key = [0u8; consts::AES_KEYSIZE];
+	let mut iv = [0u8; consts::AES_IVSIZE];
+	bytes::copy_slice(&mut key[..], &self.derived[..consts::AES_KEYSIZE]);
+	bytes::copy_slice(&mut iv[..], &self.derived[consts::AES_KEYSIZE..]);
+	aes::AesKey::new(key, iv)
     }
     /// Validate this password.
+    ///
+    /// The password is re-derived with the same number of rounds this hash was derived with.
+    /// The derived hash is compared against this one in constant time.
+    /// Hashes not derived with PBKDF2 never validate, use the matching `validate_*()` method for them.
     pub fn validate(&self, string: impl AsRef<str>, salt: &Salt) -> bool
     {
-	&Self::derive(string, salt) == self
+	self.kdf == Kdf::Pbkdf2 && bytes::ct_eq(&Self::derive_with(string, salt, self.rounds).derived[..], &self.derived[..])
     }
 
-    /// Derive a password hash from string and salt
-    pub fn derive(string: impl AsRef<str>, salt: &Salt) -> Password
+    /// Validate this password against any of several salt and round count pairs, e.g. the current and previous ones during a rotation.
+    ///
+    /// # Notes
+    /// The password is re-derived for every candidate, even after one has matched, and each is compared against this hash in constant time. The number of rounds this hash was derived with is ignored.
+    /// Hashes not derived with PBKDF2 never validate.
+    pub fn validate_any(&self, string: impl AsRef<str>, candidates: &[(Salt, u32)]) -> bool
     {
 	let string = string.as_ref();
+	self.validate_candidates(candidates, |salt, rounds| Self::derive_with(string, salt, rounds))
+    }
+
+    fn validate_candidates<F>(&self, candidates: &[(Salt, u32)], mut derive: F) -> bool
+    where F: FnMut(&Salt, u32) -> Password
+    {
+	self.kdf == Kdf::Pbkdf2 && candidates.iter().fold(false, |matched, (salt, rounds)| {
+	    matched | bytes::ct_eq(&derive(salt, *rounds).derived[..], &self.derived[..])
+	})
+    }
+
+    /// Derive a password hash from string and salt
+    #[inline] pub fn derive(string: impl AsRef<str>, salt: &Salt) -> Password
+    {
+	Self::derive_with(string, salt, ROUNDS)
+    }
+
+    /// Derive a password hash from string and salt with a specific number of rounds
+    #[inline] pub fn derive_with(string: impl AsRef<str>, salt: &Salt, rounds: u32) -> Password
+    {
+	Self::derive_bytes_with(string.as_ref().as_bytes(), salt, rounds)
+    }
+
+    /// Derive a password hash from arbitrary bytes and salt
+    ///
+    /// # Notes
+    /// Unlike `derive()`, `input` does not need to be valid UTF-8.
+    #[inline] pub fn derive_bytes(input: impl AsRef<[u8]>, salt: &Salt) -> Password
+    {
+	Self::derive_bytes_with(input, salt, ROUNDS)
+    }
+
+    /// Derive a password hash from arbitrary bytes and salt with a specific number of rounds
+    pub fn derive_bytes_with(input: impl AsRef<[u8]>, salt: &Salt, rounds: u32) -> Password
+    {
+	Self::derive_raw(input.as_ref(), &salt.0[..], rounds)
+    }
+
+    /// Validate this password against arbitrary bytes.
+    ///
+    /// The password is re-derived with the same number of rounds this hash was derived with.
+    /// The derived hash is compared against this one in constant time.
+    /// Hashes not derived with PBKDF2 never validate.
+    pub fn validate_bytes(&self, input: impl AsRef<[u8]>, salt: &Salt) -> bool
+    {
+	self.kdf == Kdf::Pbkdf2 && bytes::ct_eq(&Self::derive_bytes_with(input, salt, self.rounds).derived[..], &self.derived[..])
+    }
+
+    /// Validate this password, which was derived with a variable length salt.
+    ///
+    /// The password is re-derived with the same number of rounds this hash was derived with.
+    /// The derived hash is compared against this one in constant time.
+    /// Hashes not derived with PBKDF2 never validate.
+    pub fn validate_vec(&self, string: impl AsRef<str>, salt: &SaltVec) -> bool
+    {
+	self.kdf == Kdf::Pbkdf2 && bytes::ct_eq(&Self::derive_vec_with(string, salt, self.rounds).derived[..], &self.derived[..])
+    }
+
+    /// Derive a password hash from string and a variable length salt
+    #[inline] pub fn derive_vec(string: impl AsRef<str>, salt: &SaltVec) -> Password
+    {
+	Self::derive_vec_with(string, salt, ROUNDS)
+    }
+
+    /// Derive a password hash from string and a variable length salt with a specific number of rounds
+    pub fn derive_vec_with(string: impl AsRef<str>, salt: &SaltVec, rounds: u32) -> Password
+    {
+	Self::derive_raw(string.as_ref().as_bytes(), &salt.0[..], rounds)
+    }
+
+    /// Find the number of rounds a derivation takes about `target` to run with on this machine.
+    ///
+    /// Derivations are timed with increasing rounds until one takes long enough to measure reliably, and the count is scaled from that to `target`. The result is always at least 1.
+    ///
+    /// # Notes
+    /// This blocks for some fraction of `target` (and at least 10ms) while it measures. Call it once at startup and pass the result to `derive_with()`, rather than calibrating on every login.
+    /// The stored hash keeps the rounds it was derived with, so changing the count later does not invalidate existing hashes.
+    pub fn calibrate_rounds(target: Duration) -> u32
+    {
+	let sample = std::cmp::max(target / 10, Duration::from_millis(10));
+	let mut rounds = 1024u32;
+	loop {
+	    let start = Instant::now();
+	    Self::derive_raw(b"calibration", &STATIC_SALT[..], rounds);
+	    let elapsed = start.elapsed();
+
+	    if elapsed >= sample || rounds == u32::MAX {
+		let scaled = u128::from(rounds) * target.as_nanos() / std::cmp::max(elapsed.as_nanos(), 1);
+		return std::cmp::max(u32::try_from(scaled).unwrap_or(u32::MAX), 1);
+	    }
+	    rounds = rounds.saturating_mul(2);
+	}
+    }
+
+    fn derive_raw(input: &[u8], salt: &[u8], rounds: u32) -> Password
+    {
 	let mut derived = [0u8; KEYSIZE];
-	pbkdf2::<Hmac<Sha256>>(string.as_bytes(), &salt.0[..], ROUNDS, &mut derived[..]);
+	pbkdf2::<Hmac<Sha256>>(input, salt, rounds, &mut derived[..]);
 
-	Self{derived}
+	Self{derived, rounds, kdf: Kdf::Pbkdf2}
     }
 }
 
+/// Parameters for Argon2id password hashing
+#[cfg(feature="argon2")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature="serialise", derive(Serialize,Deserialize))]
+pub struct Argon2Params
+{
+    /// Memory cost in KiB
+    pub memory: u32,
+    /// Number of iterations
+    pub time: u32,
+    /// Degree of parallelism
+    pub parallelism: u32,
+}
+
+#[cfg(feature="argon2")]
+impl Default for Argon2Params
+{
+    #[inline]
+    fn default() -> Self
+    {
+	Self {
+	    memory: argon2::Params::DEFAULT_M_COST,
+	    time: argon2::Params::DEFAULT_T_COST,
+	    parallelism: argon2::Params::DEFAULT_P_COST,
+	}
+    }
+}
+
+#[cfg(feature="argon2")]
+impl Password
+{
+    /// Derive a password hash from string and salt using Argon2id
+    ///
+    /// # Notes
+    /// The returned hash records `Kdf::Argon2` and no rounds, it must be validated with `validate_argon2()` and not `validate()`.
+    pub fn derive_argon2(string: impl AsRef<str>, salt: &Salt, params: &Argon2Params) -> Result<Password, Error>
+    {
+	let argon = argon2::Argon2::new(argon2::Algorithm::Argon2id,
+					argon2::Version::V0x13,
+					argon2::Params::new(params.memory, params.time, params.parallelism, Some(KEYSIZE))
+					.map_err(|_| Error::Params)?);
+	let mut derived = [0u8; KEYSIZE];
+	argon.hash_password_into(string.as_ref().as_bytes(), &salt.0[..], &mut derived[..])
+	    .map_err(|_| Error::Params)?;
+
+	Ok(Self{derived, rounds: 0, kdf: Kdf::Argon2})
+    }
+
+    /// Validate this password, which was derived with `derive_argon2()`.
+    ///
+    /// The derived hash is compared against this one in constant time.
+    /// Hashes not derived with Argon2 never validate.
+    pub fn validate_argon2(&self, string: impl AsRef<str>, salt: &Salt, params: &Argon2Params) -> bool
+    {
+	match Self::derive_argon2(string, salt, params) {
+	    Ok(derived) => self.kdf == Kdf::Argon2 && bytes::ct_eq(&derived.derived[..], &self.derived[..]),
+	    Err(_) => false,
+	}
+    }
+}
+
+#[cfg(feature="scrypt")]
+impl Password
+{
+    /// Derive a password hash from string and salt using scrypt.
+    ///
+    /// `n` is the CPU/memory cost and must be a power of two greater than 1, `r` is the block size and `p` is the parallelism.
+    ///
+    /// # Notes
+    /// The returned hash records `Kdf::Scrypt` and no rounds, it must be validated with `validate_scrypt()` and not `validate()`.
+    pub fn derive_scrypt(string: impl AsRef<str>, salt: &Salt, n: u64, r: u32, p: u32) -> Result<Password, Error>
+    {
+	let mut derived = [0u8; KEYSIZE];
+	scrypt_key(string.as_ref().as_bytes(), &salt.0[..], n, r, p, &mut derived[..])?;
+
+	Ok(Self{derived, rounds: 0, kdf: Kdf::Scrypt})
+    }
+
+    /// Validate this password, which was derived with `derive_scrypt()`.
+    ///
+    /// The derived hash is compared against this one in constant time.
+    /// Hashes not derived with scrypt never validate.
+    pub fn validate_scrypt(&self, string: impl AsRef<str>, salt: &Salt, n: u64, r: u32, p: u32) -> bool
+    {
+	match Self::derive_scrypt(string, salt, n, r, p) {
+	    Ok(derived) => self.kdf == Kdf::Scrypt && bytes::ct_eq(&derived.derived[..], &self.derived[..]),
+	    Err(_) => false,
+	}
+    }
+}
+
+#[cfg(feature="scrypt")]
+fn scrypt_key(input: &[u8], salt: &[u8], n: u64, r: u32, p: u32, output: &mut [u8]) -> Result<(), Error>
+{
+    if n < 2 || !n.is_power_of_two() {
+	return Err(Error::Params);
+    }
+    let params = scrypt::Params::new(n.trailing_zeros() as u8, r, p, output.len()).map_err(|_| Error::Params)?;
+    scrypt::scrypt(input, salt, &params, output).map_err(|_| Error::Params)
+}
+
+/// The algorithm identifier used for PHC strings
+pub const PHC_ID: &str = "pbkdf2-sha256";
+
+impl Password
+{
+    /// Encode this hash and the salt it was derived with as a PHC string.
+    ///
+    /// The format is `$pbkdf2-sha256$i=<rounds>,l=<length>$<salt>$<hash>`, with the salt and hash encoded as base64 without padding.
+    ///
+    /// # Errors
+    /// Only hashes derived with PBKDF2 can be encoded, `Error::Kdf` is returned for others. A hash with no rounds cannot be encoded either, PHC requires at least one and `Error::Phc` is returned.
+    pub fn to_phc(&self, salt: impl AsRef<[u8]>) -> Result<String, Error>
+    {
+	if self.kdf != Kdf::Pbkdf2 {
+	    return Err(Error::Kdf);
+	}
+	if self.rounds == 0 {
+	    return Err(Error::Phc);
+	}
+	Ok(format!("${}$i={},l={}${}${}",
+		PHC_ID,
+		self.rounds,
+		KEYSIZE,
+		base64::encode_config(salt.as_ref(), base64::STANDARD_NO_PAD),
+		base64::encode_config(&self.derived[..], base64::STANDARD_NO_PAD)))
+    }
+
+    /// Parse a PHC string created by `to_phc()` (or another implementation) into the hash and the salt it was derived with.
+    ///
+    /// The salt can be any length, other implementations commonly use 16 bytes. The hash must be `KEYSIZE` bytes long.
+    pub fn from_phc(phc: impl AsRef<str>) -> Result<(Self, SaltVec), Error>
+    {
+	let mut parts = phc.as_ref().split('$');
+	let (params, salt, hash) = match (parts.next(), parts.next(), parts.next(), parts.next(), parts.next(), parts.next()) {
+	    (Some(""), Some(PHC_ID), Some(params), Some(salt), Some(hash), None) => (params, salt, hash),
+	    _ => return Err(Error::Phc),
+	};
+
+	let mut rounds = None;
+	let mut len = None;
+	for param in params.split(',') {
+	    match param.split_once('=') {
+		Some(("i", value)) => rounds = Some(value.parse().map_err(|_| Error::Phc)?),
+		Some(("l", value)) => len = Some(value.parse::<usize>().map_err(|_| Error::Phc)?),
+		_ => return Err(Error::Phc),
+	    }
+	}
+	let rounds = match rounds {
+	    Some(0) | None => return Err(Error::Phc),
+	    Some(rounds) => rounds,
+	};
+
+	let salt = base64::decode_config(salt, base64::STANDARD_NO_PAD).map_err(|_| Error::Phc)?;
+	let hash = base64::decode_config(hash, base64::STANDARD_NO_PAD).map_err(|_| Error::Phc)?;
+	if len.is_some_and(|len| len != hash.len()) {
+	    return Err(Error::Phc);
+	}
+	if hash.len() != KEYSIZE {
+	    return Err(Error::Length{expected: Some(KEYSIZE), got: Some(hash.len())});
+	}
+
+	let mut this = Self::empty();
+	this.rounds = rounds;
+	bytes::copy_slice(&mut this.derived[..], &hash[..]);
+	Ok((this, SaltVec::specific(salt)))
+    }
+}
+
+/// Verify a password against a PHC string.
+///
+/// Returns `Ok(false)` if the PHC string is valid but the password does not match.
+pub fn verify_phc(string: impl AsRef<str>, phc: impl AsRef<str>) -> Result<bool, Error>
+{
+    let (hash, salt) = Password::from_phc(phc)?;
+    Ok(hash.validate_vec(string, &salt))
+}
+
+/// A password hash stored together with the salt it was derived with.
+///
+/// This is everything needed to verify a password later, in one value. The number of rounds and the key derivation function are recorded in the hash.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature="serialise", derive(Serialize,Deserialize))]
+pub struct StoredPassword
+{
+    salt: Salt,
+    hash: Password,
+}
+
+impl StoredPassword
+{
+    /// Size of the binary representation created by `to_bytes()`
+    pub const SIZE: usize = 4 + 1 + SALTSIZE + KEYSIZE;
+
+    /// Combine a hash with the salt it was derived with
+    #[inline] pub const fn new(salt: Salt, hash: Password) -> Self
+    {
+	Self { salt, hash }
+    }
+
+    /// Derive a hash from a password with a new random salt and the default number of rounds
+    pub fn derive(string: impl AsRef<str>) -> Result<Self, Error>
+    {
+	let salt = Salt::random()?;
+	let hash = Password::derive(string, &salt);
+	Ok(Self { salt, hash })
+    }
+
+    /// The salt the hash was derived with
+    #[inline] pub fn salt(&self) -> &Salt
+    {
+	&self.salt
+    }
+
+    /// The password hash
+    #[inline] pub fn hash(&self) -> &Password
+    {
+	&self.hash
+    }
+
+    /// The number of PBKDF2 rounds the hash was derived with
+    #[inline] pub const fn rounds(&self) -> u32
+    {
+	self.hash.rounds
+    }
+
+    /// Consume into the salt and hash
+    #[inline] pub fn into_parts(self) -> (Salt, Password)
+    {
+	(self.salt, self.hash)
+    }
+
+    /// Check if `candidate` is the password this hash was derived from.
+    ///
+    /// The candidate is derived with the stored salt and number of rounds, and compared in constant time.
+    ///
+    /// # Notes
+    /// This is always `false` for hashes not derived with PBKDF2, whose parameters are not stored. Validate those through `hash()` with the matching `validate_*()` method.
+    #[inline] pub fn verify(&self, candidate: impl AsRef<str>) -> bool
+    {
+	self.hash.validate(candidate, &self.salt)
+    }
+
+    /// Encode as bytes.
+    ///
+    /// The format is the number of rounds as a little-endian `u32`, then one byte identifying the key derivation function, then the salt, then the hash.
+    pub fn to_bytes(&self) -> Vec<u8>
+    {
+	let mut output = Vec::with_capacity(Self::SIZE);
+	output.extend_from_slice(&self.hash.rounds.to_le_bytes()[..]);
+	output.push(self.hash.kdf.tag());
+	output.extend_from_slice(&self.salt.0[..]);
+	output.extend_from_slice(&self.hash.derived[..]);
+	output
+    }
+
+    /// Decode from bytes created by `to_bytes()`
+    ///
+    /// # Errors
+    /// `Error::Kdf` is returned if the key derivation function is unknown, or its feature is not enabled.
+    pub fn from_bytes(bytes: impl AsRef<[u8]>) -> Result<Self, Error>
+    {
+	let bytes = bytes.as_ref();
+	if bytes.len() != Self::SIZE {
+	    return Err(Error::Length{expected: Some(Self::SIZE), got: Some(bytes.len())});
+	}
+	let (rounds, bytes) = bytes.split_at(4);
+	let (kdf, bytes) = bytes.split_at(1);
+	let (salt, hash) = bytes.split_at(SALTSIZE);
+
+	let mut this = Self::new(Salt::slice(salt)?, Password::empty());
+	this.hash.rounds = u32::from_le_bytes([rounds[0], rounds[1], rounds[2], rounds[3]]);
+	this.hash.kdf = Kdf::from_tag(kdf[0]).ok_or(Error::Kdf)?;
+	bytes::copy_slice(&mut this.hash.derived[..], hash);
+	Ok(this)
+    }
+}
+
+/// Derive a key of any length from string and salt into `output`, using `rounds` rounds of PBKDF2.
+///
+/// The length of the derived key is the length of `output`.
+pub fn derive_key(string: impl AsRef<str>, salt: &Salt, rounds: u32, output: &mut [u8])
+{
+    pbkdf2::<Hmac<Sha256>>(string.as_ref().as_bytes(), &salt.0[..], rounds, output);
+}
+
+/// Derive a key like `derive_key()`, with `label` prepended to the salt.
+///
+/// Keys derived with different labels are independent of each other, and of the `Password` hash of the same string and salt.
+pub(crate) fn derive_key_labelled(label: &[u8], string: impl AsRef<str>, salt: &Salt, rounds: u32, output: &mut [u8])
+{
+    let mut salted = Vec::with_capacity(label.len() + SALTSIZE);
+    salted.extend_from_slice(label);
+    salted.extend_from_slice(&salt.0[..]);
+    pbkdf2::<Hmac<Sha256>>(string.as_ref().as_bytes(), &salted[..], rounds, output);
+}
+
 impl AsRef<[u8]> for Password
 {
     #[inline] fn as_ref(&self) -> &[u8]
@@ -180,6 +892,17 @@ impl AsMut<[u8]> for Password
     }
 }
 
+/// Does not print the derived hash, use `Display` for that.
+impl fmt::Debug for Password
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+	f.debug_struct("Password")
+	    .field("rounds", &self.rounds)
+	    .finish_non_exhaustive()
+    }
+}
+
 impl fmt::Display for Password
 {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
@@ -192,35 +915,407 @@ impl fmt::Display for Password
     }
 }
 
+#[cfg(feature="aes")]
+const _: () = assert!(KEYSIZE >= consts::AES_KEYSIZE + consts::AES_IVSIZE, "password hash too small for an aes key");
+
 #[cfg(feature="aes")]
 impl From<Password> for aes::AesKey
 {
+    /// The same as `create_aes()`, which cannot fail with the current constants.
     #[inline] fn from(from: Password) -> Self
     {
-	unsafe {
-	    std::mem::transmute(from)
-	}
+	from.aes_key()
     }
 }
 
+pub use crate::error::password::Error;
+
 #[cfg(test)]
 mod tests
 {
     use super::*;
     #[test]
-    #[cfg(feature="aes")] 
-    fn transmute_safe()
+    fn salt_from_hash()
     {
-	assert_eq!(std::mem::size_of::<Password>(), std::mem::size_of::<aes::AesKey>());
-	assert_eq!(std::mem::align_of::<Password>(), std::mem::align_of::<aes::AesKey>());
+	let hash = crate::sha256::compute_slice(b"user@example.com");
+	let salt = Salt::from(hash);
 
+	assert_eq!(salt.as_ref(), hash.as_ref());
+	assert_eq!(<[u8; SALTSIZE]>::from(salt), hash.into_bytes());
+    }
+
+    #[test]
+    fn calibrate_rounds()
+    {
+	let rounds = Password::calibrate_rounds(Duration::from_millis(50));
+	assert!(rounds > 0);
+
+	// A tenth of the target, so a busy machine during calibration doesn't make this flaky
+	let start = Instant::now();
+	let password = Password::derive_with("hello world", &Salt::embedded(), rounds);
+	assert!(start.elapsed() >= Duration::from_millis(5));
+	assert_eq!(password.rounds(), rounds);
+
+	assert_eq!(Password::calibrate_rounds(Duration::from_secs(0)), 1);
+    }
+
+    #[test]
+    fn random_source_error()
+    {
+	use std::error::Error as _;
+	fn fails() -> Result<(), Error>
+	{
+	    Err(getrandom::Error::from(std::num::NonZeroU32::new(getrandom::Error::CUSTOM_START).unwrap()))?
+	}
+
+	let err = fails().unwrap_err();
+	assert!(matches!(err, Error::RandomSource(_)));
+	assert!(err.source().is_some());
+	assert!(Error::Random.source().is_none());
+    }
+
+    #[test]
+    #[cfg(feature="aes")] 
+    fn aes_conversion()
+    {
 	let passwd = Password::derive("hello world", &Default::default());
-	let naes = passwd.create_aes();
+	let naes = passwd.create_aes().expect("create_aes");
+	assert_eq!(naes.k(), &passwd.as_ref()[..consts::AES_KEYSIZE]);
+	assert_eq!(naes.i(), &passwd.as_ref()[consts::AES_KEYSIZE..(consts::AES_KEYSIZE + consts::AES_IVSIZE)]);
+
+	#[allow(deprecated)]
+	let shim = passwd.create_aes_key();
+	assert_eq!(shim, naes);
+
 	let aes: aes::AesKey = passwd.into();
 
 	assert_eq!(aes, naes);
     }
-}
 
+    #[test]
+    fn validate()
+    {
+	let salt = Salt::random().unwrap();
+	let passwd = Password::derive("hello world", &salt);
+
+	assert!(passwd.validate("hello world", &salt));
+	assert!(!passwd.validate("hello world!", &salt));
+	assert!(!passwd.validate("", &salt));
+	assert!(!passwd.validate("hello world", &Salt::none()));
+    }
+
+    #[test]
+    fn validate_any()
+    {
+	const ROUNDS: u32 = 100;
+	let old = Salt::random().unwrap();
+	let salt = Salt::random().unwrap();
+	let passwd = Password::derive_with("hello world", &salt, ROUNDS);
+
+	assert!(passwd.validate_any("hello world", &[(old.clone(), ROUNDS), (salt.clone(), ROUNDS)]));
+	assert!(!passwd.validate_any("hello world!", &[(old.clone(), ROUNDS), (salt.clone(), ROUNDS)]));
+	assert!(!passwd.validate_any("hello world", &[(salt.clone(), ROUNDS * 2)]));
+	assert!(!passwd.validate_any("hello world", &[]));
+
+	// Candidates after the match must still be derived
+	let candidates = [(salt.clone(), ROUNDS), (old.clone(), ROUNDS), (old, ROUNDS * 2)];
+	let mut derived = Vec::new();
+	assert!(passwd.validate_candidates(&candidates[..], |salt, rounds| {
+	    derived.push((salt.clone(), rounds));
+	    Password::derive_with("hello world", salt, rounds)
+	}));
+	assert_eq!(&derived[..], &candidates[..]);
+    }
+
+    #[test]
+    fn salt_strings()
+    {
+	let salt = Salt::random().unwrap();
+
+	assert_eq!(Salt::from_hex(salt.to_hex()).unwrap(), salt);
+	assert_eq!(Salt::from_base64(salt.to_base64()).unwrap(), salt);
+	assert_eq!(Salt::embedded().to_hex(), "d0a2404173bac722b29282652f2c457b573261e3c8701b908bb0bd3ada3d7f2d");
+
+	assert!(matches!(Salt::from_hex(&salt.to_hex()[2..]), Err(Error::Length{expected: Some(SALTSIZE), got: Some(31)})));
+	assert!(matches!(Salt::from_base64(base64::encode(&[0u8; SALTSIZE + 1][..])), Err(Error::Length{expected: Some(SALTSIZE), got: Some(33)})));
+	assert!(matches!(Salt::from_hex("zz"), Err(Error::Encoding)));
+	assert!(matches!(Salt::from_base64("not base64!"), Err(Error::Encoding)));
+    }
+
+    #[test]
+    fn stored_password()
+    {
+	let stored = StoredPassword::derive("hello world").unwrap();
+	assert!(stored.verify("hello world"));
+	assert!(!stored.verify("hello world!"));
+	assert_eq!(stored.rounds(), ROUNDS);
+
+	let bytes = stored.to_bytes();
+	assert_eq!(bytes.len(), StoredPassword::SIZE);
+	assert_eq!(StoredPassword::from_bytes(&bytes).unwrap(), stored);
+	assert!(matches!(StoredPassword::from_bytes(&bytes[1..]), Err(Error::Length{expected: Some(StoredPassword::SIZE), ..})));
+	let mut unknown = bytes.clone();
+	unknown[4] = 0xff;
+	assert!(matches!(StoredPassword::from_bytes(&unknown), Err(Error::Kdf)));
+
+	let salt = Salt::random().unwrap();
+	let stored = StoredPassword::new(salt.clone(), Password::derive_with("hello world", &salt, 100));
+	let stored = StoredPassword::from_bytes(stored.to_bytes()).unwrap();
+	assert_eq!(stored.rounds(), 100);
+	assert_eq!(stored.salt(), &salt);
+	assert!(stored.verify("hello world"));
+    }
+
+    #[cfg(feature="scrypt")]
+    #[test]
+    fn stored_password_scrypt()
+    {
+	let salt = Salt::random().unwrap();
+	let stored = StoredPassword::new(salt.clone(), Password::derive_scrypt("hello world", &salt, 1024, 8, 1).unwrap());
+	let output = StoredPassword::from_bytes(stored.to_bytes()).unwrap();
+
+	assert_eq!(output, stored);
+	assert_eq!(output.hash().kdf(), Kdf::Scrypt);
+	assert!(output.hash().validate_scrypt("hello world", output.salt(), 1024, 8, 1));
+	assert!(!output.verify("hello world"));
+    }
+
+    #[cfg(feature="serialise")]
+    #[test]
+    fn stored_password_serialised()
+    {
+	let stored = StoredPassword::new(Salt::embedded(), Password::derive_with("hello world", &Salt::embedded(), 100));
+	let ser = serde_cbor::to_vec(&stored).expect("Serialise");
+	let de: StoredPassword = serde_cbor::from_slice(&ser[..]).expect("Deserialise");
+	assert_eq!(de, stored);
+	assert!(de.verify("hello world"));
+    }
+
+    #[test]
+    fn into_bytes_zeroizing()
+    {
+	let password = Password::derive("hello world", &Salt::embedded());
+	let bytes: Zeroizing<[u8; KEYSIZE]> = password.clone().into_bytes_zeroizing();
+
+	assert_eq!(&bytes[..], password.as_ref());
+	assert_eq!(*bytes, password.into_bytes());
+    }
+
+    #[test]
+    fn debug_redacted()
+    {
+	let password = Password::derive("hello world", &Salt::embedded());
+	let debug = format!("{:?}", password);
+
+	assert_eq!(debug, format!("Password {{ rounds: {}, .. }}", password.rounds()));
+	assert!(!debug.contains(&password.to_string()[..8]));
+    }
+
+    #[test]
+    fn salt_xor()
+    {
+	let salt = Salt::random().unwrap();
+	let other = Salt::random().unwrap();
+
+	assert_eq!(salt.xor(&Salt::none()), salt);
+	assert_eq!(salt.xor(&salt), Salt::none());
+	assert_eq!(salt.xor(&other), other.xor(&salt));
+	assert_eq!(salt.xor(&other).xor(&other), salt);
+	assert_eq!(Salt::specific([0xf0; SALTSIZE]).xor(&Salt::specific([0x3c; SALTSIZE])), Salt::specific([0xcc; SALTSIZE]));
+    }
+
+    #[test]
+    fn salt_ct_eq()
+    {
+	let salt = Salt::random().unwrap();
+	let mut other = salt.clone();
+
+	assert!(salt.ct_eq(&other));
+	assert!(Salt::none().ct_eq(&Salt::none()));
+	other.as_mut()[SALTSIZE - 1] ^= 1;
+	assert!(!salt.ct_eq(&other));
+	assert!(!Salt::embedded().ct_eq(&Salt::none()));
+    }
+
+    #[test]
+    fn salt_with_rng()
+    {
+	use rand_chacha::{ChaCha20Rng, rand_core::SeedableRng};
+
+	let salt = Salt::random_with_rng(ChaCha20Rng::seed_from_u64(1)).unwrap();
+	assert_eq!(Salt::random_with_rng(ChaCha20Rng::seed_from_u64(1)).unwrap(), salt);
+	assert_ne!(salt, Salt::embedded());
+
+	let salt = SaltVec::random_len_with_rng(7, ChaCha20Rng::seed_from_u64(1)).unwrap();
+	assert_eq!(salt.len(), 7);
+	assert_eq!(SaltVec::random_len_with_rng(7, ChaCha20Rng::seed_from_u64(1)).unwrap(), salt);
+    }
+
+    #[test]
+    fn derive_bytes()
+    {
+	let salt = Salt::random().unwrap();
+	let input = [0xffu8, 0xfe, 0x00, 0xc3, 0x28];
+	assert!(std::str::from_utf8(&input[..]).is_err());
+
+	let passwd = Password::derive_bytes(&input[..], &salt);
+	assert!(passwd.validate_bytes(&input[..], &salt));
+	assert!(!passwd.validate_bytes(&input[1..], &salt));
+	assert_eq!(Password::derive_bytes("hello world", &salt), Password::derive("hello world", &salt));
+    }
+
+    #[test]
+    fn salt_vec()
+    {
+	let salt = SaltVec::random_len(16).unwrap();
+	assert_eq!(salt.len(), 16);
+
+	let passwd = Password::derive_vec("hello world", &salt);
+	assert!(passwd.validate_vec("hello world", &salt));
+	assert!(!passwd.validate_vec("hello world!", &salt));
+	assert!(!passwd.validate_vec("hello world", &SaltVec::random_len(16).unwrap()));
+
+	let fixed = Salt::random().unwrap();
+	assert_eq!(Password::derive_vec("hello world", &fixed.clone().into()), Password::derive("hello world", &fixed));
+    }
+
+    #[test]
+    fn rounds()
+    {
+	let salt = Salt::random().unwrap();
+	let default = Password::derive("hello world", &salt);
+	let more = Password::derive_with("hello world", &salt, ROUNDS * 2);
+
+	assert_eq!(default.rounds(), ROUNDS);
+	assert_eq!(more.rounds(), ROUNDS * 2);
+	assert_ne!(default.as_ref(), more.as_ref());
+
+	assert!(more.validate("hello world", &salt));
+	assert!(!more.validate("hello world!", &salt));
+
+	assert!(default.needs_rehash(8192));
+	assert!(!default.needs_rehash(4096));
+	assert!(!more.needs_rehash(ROUNDS));
+
+	let mut key = [0u8; KEYSIZE * 2];
+	derive_key("hello world", &salt, ROUNDS, &mut key[..]);
+	assert_eq!(&key[..KEYSIZE], default.as_ref());
+    }
+
+    #[test]
+    #[cfg(feature="serialise")]
+    fn rounds_serialised()
+    {
+	let salt = Salt::random().unwrap();
+	let passwd = Password::derive_with("hello world", &salt, 8192);
+
+	let value = serde_cbor::to_vec(&passwd).expect("ser");
+	let output: Password = serde_cbor::from_slice(&value[..]).expect("de");
+
+	assert_eq!(output, passwd);
+	assert_eq!(output.rounds(), 8192);
+	assert!(output.validate("hello world", &salt));
+
+	// Old format without stored rounds
+	#[derive(Serialize)]
+	struct Old {
+	    derived: [u8; KEYSIZE],
+	}
+	let old = Password::derive("hello world", &salt);
+	let value = serde_cbor::to_vec(&Old{ derived: old.clone().into_bytes() }).expect("ser");
+	let output: Password = serde_cbor::from_slice(&value[..]).expect("de");
+	assert_eq!(output, old);
+	assert!(output.validate("hello world", &salt));
+    }
+
+    #[test]
+    fn phc()
+    {
+	let salt = Salt::random().unwrap();
+	let passwd = Password::derive_with("hello world", &salt, 1234);
+	let phc = passwd.to_phc(&salt).unwrap();
+	assert!(phc.starts_with("$pbkdf2-sha256$i=1234,l=32$"));
+
+	let (output, osalt) = Password::from_phc(&phc).expect("parse");
+	assert_eq!(output, passwd);
+	assert_eq!(osalt, SaltVec::from(salt.clone()));
+	assert!(verify_phc("hello world", &phc).unwrap());
+	assert!(!verify_phc("hello world!", &phc).unwrap());
+
+	assert!(Password::from_phc("$pbkdf2-sha512$i=1234$AAAA$AAAA").is_err());
+	assert!(Password::from_phc("not a phc string").is_err());
+	assert!(Password::from_phc(phc.replace("i=1234", "i=0")).is_err());
+	assert!(Password::from_phc(phc.replace("l=32", "l=16")).is_err());
+	assert!(matches!(Password::from_bytes_with_rounds(passwd.clone().into_bytes(), 0).to_phc(&salt), Err(Error::Phc)));
+    }
+
+    #[test]
+    fn phc_known()
+    {
+	// Generated by the RustCrypto `pbkdf2` crate
+	const PHC: &str = "$pbkdf2-sha256$i=4096,l=32$AAECAwQFBgcICQoLDA0ODxAREhMUFRYXGBkaGxwdHh8$nxjYCCYMvApxJfIJvh7zh4YAe28FrCFyGgLHOV2ZEeE";
+	assert!(verify_phc("correct horse battery staple", PHC).unwrap());
+	assert!(!verify_phc("incorrect horse battery staple", PHC).unwrap());
+
+	let (passwd, salt) = Password::from_phc(PHC).unwrap();
+	assert_eq!(passwd.to_phc(&salt).unwrap(), PHC);
+
+	// The same password with a 16 byte salt, as most other implementations use
+	const SHORT: &str = "$pbkdf2-sha256$i=4096,l=32$AAECAwQFBgcICQoLDA0ODw$xBIKCXrlo8ePcCxMinGbwvwO3gODLPkVyo2W2gmmj2Y";
+	assert!(verify_phc("correct horse battery staple", SHORT).unwrap());
+	let (passwd, salt) = Password::from_phc(SHORT).unwrap();
+	assert_eq!(salt.len(), 16);
+	assert_eq!(passwd.to_phc(&salt).unwrap(), SHORT);
+    }
+
+    #[test]
+    #[cfg(feature="argon2")]
+    fn argon2()
+    {
+	let salt = Salt::random().unwrap();
+	let params = Argon2Params::default();
+	let passwd = Password::derive_argon2("hello world", &salt, &params).expect("derive");
+
+	assert!(passwd.validate_argon2("hello world", &salt, &params));
+	assert!(!passwd.validate_argon2("hello world!", &salt, &params));
+	assert!(!passwd.validate_argon2("hello world", &salt, &Argon2Params{ time: params.time + 1, ..params }));
+	assert_ne!(passwd.as_ref(), Password::derive("hello world", &salt).as_ref());
+
+	assert_eq!(passwd.kdf(), Kdf::Argon2);
+	assert!(!passwd.validate("hello world", &salt));
+	assert!(!passwd.validate_bytes("hello world", &salt));
+	assert!(!passwd.validate_vec("hello world", &SaltVec::from(salt.clone())));
+	assert!(!passwd.needs_rehash(ROUNDS));
+	assert!(matches!(passwd.to_phc(&salt), Err(Error::Kdf)));
+	assert!(!Password::from_bytes(passwd.clone().into_bytes()).validate_argon2("hello world", &salt, &params));
+
+	assert!(Password::derive_argon2("hello world", &salt, &Argon2Params{ parallelism: 0, ..params }).is_err());
+    }
+
+    #[test]
+    #[cfg(feature="scrypt")]
+    fn scrypt()
+    {
+	// RFC 7914 test vector 1
+	let mut key = [0u8; KEYSIZE];
+	scrypt_key(b"", b"", 16, 1, 1, &mut key[..]).expect("scrypt");
+	assert_eq!(key, hex!("77d6576238657b203b19ca42c18a0497f16b4844e3074ae8dfdffa3fede21442"));
+
+	let salt = Salt::random().unwrap();
+	let passwd = Password::derive_scrypt("hello world", &salt, 1024, 8, 1).expect("derive");
+	assert!(passwd.validate_scrypt("hello world", &salt, 1024, 8, 1));
+	assert!(!passwd.validate_scrypt("hello world!", &salt, 1024, 8, 1));
+
+	assert_eq!(passwd.kdf(), Kdf::Scrypt);
+	assert!(!passwd.validate("hello world", &salt));
+	assert!(!passwd.validate_bytes("hello world", &salt));
+	assert!(!passwd.validate_vec("hello world", &SaltVec::from(salt.clone())));
+	assert!(!passwd.needs_rehash(ROUNDS));
+	assert!(matches!(passwd.to_phc(&salt), Err(Error::Kdf)));
+	assert!(!Password::from_bytes(passwd.clone().into_bytes()).validate_scrypt("hello world", &salt, 1024, 8, 1));
+
+	assert!(Password::derive_scrypt("hello world", &salt, 1000, 8, 1).is_err());
+	assert!(Password::derive_scrypt("hello world", &salt, 1, 8, 1).is_err());
+    }
+}
 
-pub use crate::error::password::Error;
@@ -29,6 +29,27 @@ pub trait HasPublicComponents: HasComponents
     {
 	BigNum::from_slice(self.e()).unwrap()
     }
+
+    /// The size of the modulus in bits.
+    ///
+    /// # Notes
+    /// Computed from the modulus component directly, without building an openssl key.
+    fn modulus_bits(&self) -> u32
+    {
+	let n = self.n();
+	match n.iter().position(|&byte| byte != 0) {
+	    Some(first) => ((n.len() - first - 1) * 8) as u32 + (8 - n[first].leading_zeros()),
+	    None => 0,
+	}
+    }
+
+    /// The size of the modulus in bytes.
+    ///
+    /// This is the size of a signature or of a single encrypted block made with this key, the same as openssl's `Rsa::size()`.
+    #[inline] fn size_bytes(&self) -> usize
+    {
+	self.modulus_bits().div_ceil(8) as usize
+    }
 }
 
 pub trait HasPrivateComponents: HasPublicComponents
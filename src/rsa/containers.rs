@@ -26,9 +26,10 @@ pub trait PublicKey
     fn get_pkey_pub(&self) -> Result<Cow<'_, PKey<Self::KeyType>>, Self::Error>;
 
     /// Get or create an `Rsa` from this public key if possible
+    #[allow(clippy::type_complexity)]
     #[inline] fn get_rsa_pub(&self) -> Result<Option<Cow<'_, Rsa<Self::KeyType>>>, Self::Error>
     {
-	Ok(self.get_pkey_pub()?.rsa().ok().map(|x| Cow::Owned(x)))
+	Ok(self.get_pkey_pub()?.rsa().ok().map(Cow::Owned))
     }
 }
 
@@ -42,6 +43,7 @@ where <Self as PublicKey>::KeyType: HasPrivate
 	self.get_pkey_pub()
     }
     /// Get or create an `Rsa` from this private key if possible
+    #[allow(clippy::type_complexity)]
     #[inline] fn get_rsa_priv(&self) -> Result<Option<Cow<'_, Rsa<Self::KeyType>>>, Self::Error>
     {
 	self.get_rsa_pub()
@@ -5,12 +5,14 @@ use std::{
     convert::TryFrom,
     marker::Unpin,
     io::{
+	self,
 	Write,
 	Read,
     },
 };
 use openssl::{
     pkey::HasPrivate,
+    rsa::Padding,
 };
 #[cfg(feature="async")]
 use tokio::io::{
@@ -21,33 +23,128 @@ use tokio::io::{
 };
 
 use consts::RSA_PADDING_NEEDS as PADDING_NEEDS;
+use consts::RSA_OAEP_PADDING_NEEDS as OAEP_PADDING_NEEDS;
+
+/// Size of the length prefix written before each ciphertext block by the streaming functions
+///
+/// # Notes
+/// The prefix is a little-endian `u32`.
+const BLOCK_PREFIX: usize = std::mem::size_of::<u32>();
+
+/// The number of bytes of each block that `padding` takes up, or `None` if the encryption functions can't use it
+fn padding_needs(padding: Padding) -> Option<usize>
+{
+    match padding {
+	Padding::PKCS1 => Some(PADDING_NEEDS),
+	Padding::PKCS1_OAEP => Some(OAEP_PADDING_NEEDS),
+	_ => None,
+    }
+}
+
+/// Read the length prefix of the next ciphertext block. Returns `None` if the stream ended cleanly before it.
+#[cfg(feature="async")]
+async fn read_block_len<T>(from: &mut T) -> io::Result<Option<usize>>
+where T: AsyncRead + Unpin + ?Sized
+{
+    let mut buffer = [0u8; BLOCK_PREFIX];
+    let mut done = 0;
+    while done < buffer.len() {
+	match from.read(&mut buffer[done..]).await? {
+	    0 if done == 0 => return Ok(None),
+	    0 => return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "couldn't read block length")),
+	    read => done += read,
+	}
+    }
+    Ok(Some(u32::from_le_bytes(buffer) as usize))
+}
+
+/// Read the length prefix of the next ciphertext block. Returns `None` if the stream ended cleanly before it.
+fn read_block_len_sync<T>(from: &mut T) -> io::Result<Option<usize>>
+where T: Read + ?Sized
+{
+    let mut buffer = [0u8; BLOCK_PREFIX];
+    let mut done = 0;
+    while done < buffer.len() {
+	match from.read(&mut buffer[done..])? {
+	    0 if done == 0 => return Ok(None),
+	    0 => return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "couldn't read block length")),
+	    read => done += read,
+	}
+    }
+    Ok(Some(u32::from_le_bytes(buffer) as usize))
+}
 
 /// Encrypt a slice `data` to a new output vector with key `key`
-pub fn encrypt_slice_to_vec<T,K>(data: T, key: &K) -> Result<Vec<u8>, Error>
+///
+/// # Errors
+/// `Error::TooLarge` if `data` is larger than `key` can encrypt in one block, use `encrypt_sync()` for larger inputs.
+#[inline] pub fn encrypt_slice_to_vec<T,K>(data: T, key: &K) -> Result<Vec<u8>, Error>
+where T: AsRef<[u8]>,
+      K: PublicKey + ?Sized,
+{
+    encrypt_slice_to_vec_with(data, key, PADDING)
+}
+
+/// Encrypt a slice `data` to a new output vector with key `key`, using a specific padding mode.
+///
+/// # Notes
+/// Only `Padding::PKCS1` and `Padding::PKCS1_OAEP` are supported, others fail with `Error::Encrypt`.
+///
+/// # Errors
+/// `Error::TooLarge` if `data` is larger than `key` can encrypt in one block with `padding`, use `encrypt_sync_with()` for larger inputs.
+pub fn encrypt_slice_to_vec_with<T,K>(data: T, key: &K, padding: Padding) -> Result<Vec<u8>, Error>
 where T: AsRef<[u8]>,
       K: PublicKey + ?Sized,
 {
     let data = data.as_ref();
     let mut output = Vec::with_capacity(data.len());
-    encrypt_slice_sync(data, key, &mut output)?;
+    encrypt_slice_sync_with(data, key, &mut output, padding)?;
     Ok(output)
 }
 
 /// Decrypt a slice `data` to a new output vector with key `key`
-pub fn decrypt_slice_to_vec<T,K>(data: T, key: &K) -> Result<Vec<u8>, Error>
+#[inline] pub fn decrypt_slice_to_vec<T,K>(data: T, key: &K) -> Result<Vec<u8>, Error>
+where T: AsRef<[u8]>,
+      K: PrivateKey + ?Sized,
+<K as PublicKey>::KeyType: HasPrivate,
+{
+    decrypt_slice_to_vec_with(data, key, PADDING)
+}
+
+/// Decrypt a slice `data` to a new output vector with key `key`, using a specific padding mode.
+///
+/// # Notes
+/// Only `Padding::PKCS1` and `Padding::PKCS1_OAEP` are supported, others fail with `Error::Decrypt`.
+pub fn decrypt_slice_to_vec_with<T,K>(data: T, key: &K, padding: Padding) -> Result<Vec<u8>, Error>
 where T: AsRef<[u8]>,
       K: PrivateKey + ?Sized,
 <K as PublicKey>::KeyType: HasPrivate,
 {
     let data = data.as_ref();
     let mut output = Vec::with_capacity(data.len());
-    decrypt_slice_sync(data, key, &mut output)?;
+    decrypt_slice_sync_with(data, key, &mut output, padding)?;
     Ok(output)
 }
 
 /// Encrypt a stream `data` into `output` with `key`. Return the number of bytes *read*.
+///
+/// # Notes
+/// Each ciphertext block is written prefixed with its length. Use `decrypt()`/`decrypt_sync()` to read it back.
+#[cfg(feature="async")] 
+#[inline] pub async fn encrypt<T,K,U>(data: &mut T, key: &K, output: &mut U) -> Result<usize, Error>
+where T: AsyncRead + Unpin + ?Sized,
+      K: PublicKey + ?Sized,
+      U: AsyncWrite + Unpin + ?Sized
+{
+    encrypt_with(data, key, output, PADDING).await
+}
+
+/// Encrypt a stream `data` into `output` with `key`, using a specific padding mode. Return the number of bytes *read*.
+///
+/// # Notes
+/// Only `Padding::PKCS1` and `Padding::PKCS1_OAEP` are supported, others fail with `Error::Encrypt`. The stream must be decrypted with the same padding.
 #[cfg(feature="async")] 
-pub async fn encrypt<T,K,U>(data: &mut T, key: &K, output: &mut U) -> Result<usize, Error>
+pub async fn encrypt_with<T,K,U>(data: &mut T, key: &K, output: &mut U, padding: Padding) -> Result<usize, Error>
 where T: AsyncRead + Unpin + ?Sized,
       K: PublicKey + ?Sized,
       U: AsyncWrite + Unpin + ?Sized
@@ -55,7 +152,7 @@ where T: AsyncRead + Unpin + ?Sized,
     let key = key.get_rsa_pub().map_err(|_| Error::Key)?.ok_or(Error::Key)?;
     let key_size = usize::try_from(key.size())?;
 
-    let max_size = key_size - PADDING_NEEDS;
+    let max_size = key_size - padding_needs(padding).ok_or(Error::Encrypt)?;
 
     let mut read_buffer = vec![0u8; max_size];
     let mut crypt_buffer = vec![0u8; key_size];
@@ -64,7 +161,8 @@ where T: AsyncRead + Unpin + ?Sized,
     let mut done=0;
     while {read = data.read(&mut read_buffer[..]).await?; read!=0} {
 	done+=read;
-	read = key.public_encrypt(&read_buffer[..read], &mut crypt_buffer[..], PADDING).map_err(|_| Error::Encrypt)?;
+	read = key.public_encrypt(&read_buffer[..read], &mut crypt_buffer[..], padding).map_err(|_| Error::Encrypt)?;
+	output.write_all(&u32::try_from(read)?.to_le_bytes()).await?;
 	output.write_all(&crypt_buffer[..read]).await?;
     }
     
@@ -72,24 +170,54 @@ where T: AsyncRead + Unpin + ?Sized,
 }
 
 /// Encrypt a slice `data` into `output` with `key`. Return the number of bytes *written*.
+///
+/// # Errors
+/// `Error::TooLarge` if `data` is larger than `key` can encrypt in one block, use `encrypt()`/`encrypt_sync()` for larger inputs.
 #[cfg(feature="async")] 
-pub async fn encrypt_slice<T,K,U>(data: T, key: &K, output: &mut U) -> Result<usize, Error>
+#[inline] pub async fn encrypt_slice<T,K,U>(data: T, key: &K, output: &mut U) -> Result<usize, Error>
 where T: AsRef<[u8]>,
       K: PublicKey + ?Sized,
       U: AsyncWrite + Unpin + ?Sized
 {
-    let key = key.get_rsa_pub().map_err(|_| Error::Key)?.ok_or(Error::Key)?;
-    let key_size = usize::try_from(key.size())?;
-
-    let mut crypt_buffer = vec![0u8; key_size];
+    encrypt_slice_with(data, key, output, PADDING).await
+}
 
-    let read = key.public_encrypt(data.as_ref(), &mut crypt_buffer[..], PADDING).map_err(|_| Error::Encrypt)?;
-    output.write_all(&crypt_buffer[..read]).await?;
+/// Encrypt a slice `data` into `output` with `key`, using a specific padding mode. Return the number of bytes *written*.
+///
+/// # Notes
+/// Only `Padding::PKCS1` and `Padding::PKCS1_OAEP` are supported, others fail with `Error::Encrypt`.
+///
+/// # Errors
+/// `Error::TooLarge` if `data` is larger than `key` can encrypt in one block with `padding`, use `encrypt_with()`/`encrypt_sync_with()` for larger inputs.
+#[cfg(feature="async")] 
+pub async fn encrypt_slice_with<T,K,U>(data: T, key: &K, output: &mut U, padding: Padding) -> Result<usize, Error>
+where T: AsRef<[u8]>,
+      K: PublicKey + ?Sized,
+      U: AsyncWrite + Unpin + ?Sized
+{
+    let mut crypt_buffer = Vec::new();
+    let read = encrypt_slice_sync_with(data, key, &mut crypt_buffer, padding)?;
+    output.write_all(&crypt_buffer[..]).await?;
 
     Ok(read)
 }
 /// Encrypt a stream `data` into `output` with `key`. Return the number of bytes *read*.
-pub fn encrypt_sync<T,K,U>(data: &mut T, key: &K, output: &mut U) -> Result<usize, Error>
+///
+/// # Notes
+/// Each ciphertext block is written prefixed with its length. Use `decrypt()`/`decrypt_sync()` to read it back.
+#[inline] pub fn encrypt_sync<T,K,U>(data: &mut T, key: &K, output: &mut U) -> Result<usize, Error>
+where T: Read + ?Sized,
+      K: PublicKey + ?Sized,
+      U: Write + ?Sized
+{
+    encrypt_sync_with(data, key, output, PADDING)
+}
+
+/// Encrypt a stream `data` into `output` with `key`, using a specific padding mode. Return the number of bytes *read*.
+///
+/// # Notes
+/// Only `Padding::PKCS1` and `Padding::PKCS1_OAEP` are supported, others fail with `Error::Encrypt`. The stream must be decrypted with the same padding.
+pub fn encrypt_sync_with<T,K,U>(data: &mut T, key: &K, output: &mut U, padding: Padding) -> Result<usize, Error>
 where T: Read + ?Sized,
       K: PublicKey + ?Sized,
       U: Write + ?Sized
@@ -97,7 +225,7 @@ where T: Read + ?Sized,
     let key = key.get_rsa_pub().map_err(|_| Error::Key)?.ok_or(Error::Key)?;
     let key_size = usize::try_from(key.size())?;
 
-    let max_size = key_size - PADDING_NEEDS;
+    let max_size = key_size - padding_needs(padding).ok_or(Error::Encrypt)?;
 
     let mut read_buffer = vec![0u8; max_size];
     let mut crypt_buffer = vec![0u8; key_size];
@@ -106,8 +234,9 @@ where T: Read + ?Sized,
     let mut done=0;
     while {read = data.read(&mut read_buffer[..])?; read!=0} {
 	done+=read;
-	read = key.public_encrypt(&read_buffer[..read], &mut crypt_buffer[..], PADDING).map_err(|ssl| {eprintln!("SSL err: {}", ssl); Error::Encrypt})?;
+	read = key.public_encrypt(&read_buffer[..read], &mut crypt_buffer[..], padding).map_err(|_| Error::Encrypt)?;
 
+	output.write_all(&u32::try_from(read)?.to_le_bytes())?;
 	output.write_all(&crypt_buffer[..read])?;
     }
     
@@ -115,7 +244,25 @@ where T: Read + ?Sized,
 }
 
 /// Encrypt a slice `data` into `output` with `key`. Return the number of bytes *written*.
-pub fn encrypt_slice_sync<T,K,U>(data: T, key: &K, output: &mut U) -> Result<usize, Error>
+///
+/// # Errors
+/// `Error::TooLarge` if `data` is larger than `key` can encrypt in one block, use `encrypt()`/`encrypt_sync()` for larger inputs.
+#[inline] pub fn encrypt_slice_sync<T,K,U>(data: T, key: &K, output: &mut U) -> Result<usize, Error>
+where T: AsRef<[u8]>,
+      K: PublicKey + ?Sized,
+      U: Write + ?Sized
+{
+    encrypt_slice_sync_with(data, key, output, PADDING)
+}
+
+/// Encrypt a slice `data` into `output` with `key`, using a specific padding mode. Return the number of bytes *written*.
+///
+/// # Notes
+/// Only `Padding::PKCS1` and `Padding::PKCS1_OAEP` are supported, others fail with `Error::Encrypt`.
+///
+/// # Errors
+/// `Error::TooLarge` if `data` is larger than `key` can encrypt in one block with `padding`, use `encrypt_with()`/`encrypt_sync_with()` for larger inputs.
+pub fn encrypt_slice_sync_with<T,K,U>(data: T, key: &K, output: &mut U, padding: Padding) -> Result<usize, Error>
 where T: AsRef<[u8]>,
       K: PublicKey + ?Sized,
       U: Write + ?Sized
@@ -123,9 +270,15 @@ where T: AsRef<[u8]>,
     let key = key.get_rsa_pub().map_err(|_| Error::Key)?.ok_or(Error::Key)?;
     let key_size = usize::try_from(key.size())?;
 
+    let data = data.as_ref();
+    let max_size = key_size.saturating_sub(padding_needs(padding).ok_or(Error::Encrypt)?);
+    if data.len() > max_size {
+	return Err(Error::TooLarge{max: max_size, got: data.len()});
+    }
+
     let mut crypt_buffer = vec![0u8; key_size];
 
-    let read = key.public_encrypt(data.as_ref(), &mut crypt_buffer[..], PADDING).map_err(|ssl| {eprintln!("SSL err: {}", ssl); Error::Encrypt})?;
+    let read = key.public_encrypt(data, &mut crypt_buffer[..], padding).map_err(|_| Error::Encrypt)?;
     output.write_all(&crypt_buffer[..read])?;
 
     Ok(read)
@@ -133,45 +286,75 @@ where T: AsRef<[u8]>,
 
 /// Decrypt slice `data` into `output` with `key`. Return the number of bytes *written*.
 #[cfg(feature="async")] 
-pub async fn decrypt_slice<T,K,U>(data: T, key: &K, output: &mut U) -> Result<usize, Error>
+#[inline] pub async fn decrypt_slice<T,K,U>(data: T, key: &K, output: &mut U) -> Result<usize, Error>
 where T: AsRef<[u8]>,
       K: PrivateKey + ?Sized,
       U: AsyncWrite + Unpin + ?Sized,
 <K as PublicKey>::KeyType: HasPrivate,
 {
-    
-    let key = key.get_rsa_priv().map_err(|_| Error::Key)?.ok_or(Error::Key)?;
-    let key_size = usize::try_from(key.size())?;
-
-    let mut crypt_buffer = vec![0u8; key_size];
+    decrypt_slice_with(data, key, output, PADDING).await
+}
 
-    let read = key.private_decrypt(data.as_ref(), &mut crypt_buffer[..], PADDING).map_err(|_| Error::Decrypt)?;
-    output.write_all(&crypt_buffer[..read]).await?;
+/// Decrypt slice `data` into `output` with `key`, using a specific padding mode. Return the number of bytes *written*.
+///
+/// # Notes
+/// Only `Padding::PKCS1` and `Padding::PKCS1_OAEP` are supported, others fail with `Error::Decrypt`.
+#[cfg(feature="async")] 
+pub async fn decrypt_slice_with<T,K,U>(data: T, key: &K, output: &mut U, padding: Padding) -> Result<usize, Error>
+where T: AsRef<[u8]>,
+      K: PrivateKey + ?Sized,
+      U: AsyncWrite + Unpin + ?Sized,
+<K as PublicKey>::KeyType: HasPrivate,
+{
+    let mut crypt_buffer = Vec::new();
+    let read = decrypt_slice_sync_with(data, key, &mut crypt_buffer, padding)?;
+    output.write_all(&crypt_buffer[..]).await?;
 
     Ok(read)
 }
 
 /// Decrypt a stream `data` into `output` with `key`. Return the number of bytes *read*.
+///
+/// # Notes
+/// The stream must be in the length-prefixed format written by `encrypt()`/`encrypt_sync()`.
+#[cfg(feature="async")] 
+#[inline] pub async fn decrypt<T,K,U>(data: &mut T, key: &K, output: &mut U) -> Result<usize, Error>
+where T: AsyncRead + Unpin + ?Sized,
+      K: PrivateKey + ?Sized,
+      U: AsyncWrite + Unpin + ?Sized,
+<K as PublicKey>::KeyType: HasPrivate,
+{
+    decrypt_with(data, key, output, PADDING).await
+}
+
+/// Decrypt a stream `data` into `output` with `key`, using a specific padding mode. Return the number of bytes *read*.
+///
+/// # Notes
+/// The stream must be in the length-prefixed format written by `encrypt_with()`/`encrypt_sync_with()` with the same padding.
+/// Only `Padding::PKCS1` and `Padding::PKCS1_OAEP` are supported, others fail with `Error::Decrypt`.
 #[cfg(feature="async")] 
-pub async fn decrypt<T,K,U>(data: &mut T, key: &K, output: &mut U) -> Result<usize, Error>
+pub async fn decrypt_with<T,K,U>(data: &mut T, key: &K, output: &mut U, padding: Padding) -> Result<usize, Error>
 where T: AsyncRead + Unpin + ?Sized,
       K: PrivateKey + ?Sized,
       U: AsyncWrite + Unpin + ?Sized,
 <K as PublicKey>::KeyType: HasPrivate,
 {
+    padding_needs(padding).ok_or(Error::Decrypt)?;
     let key = key.get_rsa_priv().map_err(|_| Error::Key)?.ok_or(Error::Key)?;
     let key_size = usize::try_from(key.size())?;
 
-    let max_size = key_size - PADDING_NEEDS;
-
-    let mut read_buffer = vec![0u8; max_size];
+    let mut read_buffer = vec![0u8; key_size];
     let mut crypt_buffer = vec![0u8; key_size];
 
-    let mut read;
     let mut done=0;
-    while {read = data.read(&mut read_buffer[..]).await?; read!=0} {
-	done+=read;
-	read = key.private_decrypt(&read_buffer[..read], &mut crypt_buffer[..], PADDING).map_err(|_| Error::Decrypt)?;
+    while let Some(len) = read_block_len(data).await? {
+	if len > key_size {
+	    return Err(Error::Binary(BinaryErrorKind::Length{expected: Some(key_size), got: Some(len)}));
+	}
+	data.read_exact(&mut read_buffer[..len]).await?;
+	done+=BLOCK_PREFIX + len;
+
+	let read = key.private_decrypt(&read_buffer[..len], &mut crypt_buffer[..], padding).map_err(|_| Error::Decrypt)?;
 	output.write_all(&crypt_buffer[..read]).await?;
     }
     
@@ -180,47 +363,195 @@ where T: AsyncRead + Unpin + ?Sized,
 }
 
 /// Decrypt slice `data` into `output` with `key`. Return the number of bytes *written*.
-pub fn decrypt_slice_sync<T,K,U>(data: T, key: &K, output: &mut U) -> Result<usize, Error>
+#[inline] pub fn decrypt_slice_sync<T,K,U>(data: T, key: &K, output: &mut U) -> Result<usize, Error>
 where T: AsRef<[u8]>,
       K: PrivateKey + ?Sized,
       U: Write + ?Sized,
 <K as PublicKey>::KeyType: HasPrivate,
 {
-    
+    decrypt_slice_sync_with(data, key, output, PADDING)
+}
+
+/// Decrypt slice `data` into `output` with `key`, using a specific padding mode. Return the number of bytes *written*.
+///
+/// # Notes
+/// Only `Padding::PKCS1` and `Padding::PKCS1_OAEP` are supported, others fail with `Error::Decrypt`.
+pub fn decrypt_slice_sync_with<T,K,U>(data: T, key: &K, output: &mut U, padding: Padding) -> Result<usize, Error>
+where T: AsRef<[u8]>,
+      K: PrivateKey + ?Sized,
+      U: Write + ?Sized,
+<K as PublicKey>::KeyType: HasPrivate,
+{
+    padding_needs(padding).ok_or(Error::Decrypt)?;
     let key = key.get_rsa_priv().map_err(|_| Error::Key)?.ok_or(Error::Key)?;
     let key_size = usize::try_from(key.size())?;
 
     let mut crypt_buffer = vec![0u8; key_size];
 
-    let read = key.private_decrypt(data.as_ref(), &mut crypt_buffer[..], PADDING).map_err(|_| Error::Decrypt)?;
+    let read = key.private_decrypt(data.as_ref(), &mut crypt_buffer[..], padding).map_err(|_| Error::Decrypt)?;
     output.write_all(&crypt_buffer[..read])?;
 
     Ok(read)
 }
 
 /// Decrypt a stream `data` into `output` with `key`. Return the number of bytes *read*.
-pub fn decrypt_sync<T,K,U>(data: &mut T, key: &K, output: &mut U) -> Result<usize, Error>
+///
+/// # Notes
+/// The stream must be in the length-prefixed format written by `encrypt()`/`encrypt_sync()`.
+#[inline] pub fn decrypt_sync<T,K,U>(data: &mut T, key: &K, output: &mut U) -> Result<usize, Error>
+where T: Read + ?Sized,
+      K: PrivateKey + ?Sized,
+      U: Write + ?Sized,
+<K as PublicKey>::KeyType: HasPrivate,
+{
+    decrypt_sync_with(data, key, output, PADDING)
+}
+
+/// Decrypt a stream `data` into `output` with `key`, using a specific padding mode. Return the number of bytes *read*.
+///
+/// # Notes
+/// The stream must be in the length-prefixed format written by `encrypt_with()`/`encrypt_sync_with()` with the same padding.
+/// Only `Padding::PKCS1` and `Padding::PKCS1_OAEP` are supported, others fail with `Error::Decrypt`.
+pub fn decrypt_sync_with<T,K,U>(data: &mut T, key: &K, output: &mut U, padding: Padding) -> Result<usize, Error>
 where T: Read + ?Sized,
       K: PrivateKey + ?Sized,
       U: Write + ?Sized,
 <K as PublicKey>::KeyType: HasPrivate,
 {
+    padding_needs(padding).ok_or(Error::Decrypt)?;
     let key = key.get_rsa_priv().map_err(|_| Error::Key)?.ok_or(Error::Key)?;
     let key_size = usize::try_from(key.size())?;
 
-    let max_size = key_size - PADDING_NEEDS;
-
-    let mut read_buffer = vec![0u8; max_size];
+    let mut read_buffer = vec![0u8; key_size];
     let mut crypt_buffer = vec![0u8; key_size];
 
-    let mut read;
     let mut done=0;
-    while {read = data.read(&mut read_buffer[..])?; read!=0} {
-	done+=read;
-	read = key.private_decrypt(&read_buffer[..read], &mut crypt_buffer[..], PADDING).map_err(|_| Error::Decrypt)?;
+    while let Some(len) = read_block_len_sync(data)? {
+	if len > key_size {
+	    return Err(Error::Binary(BinaryErrorKind::Length{expected: Some(key_size), got: Some(len)}));
+	}
+	data.read_exact(&mut read_buffer[..len])?;
+	done+=BLOCK_PREFIX + len;
+
+	let read = key.private_decrypt(&read_buffer[..len], &mut crypt_buffer[..], padding).map_err(|_| Error::Decrypt)?;
 	output.write_all(&crypt_buffer[..read])?;
     }
     
     Ok(done)
 
 }
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn stream_round_trip_sync()
+    {
+	let key = RsaPrivateKey::generate().unwrap();
+	let data: Vec<u8> = (0..(consts::RSA_SIG_SIZE - PADDING_NEEDS) * 3 + 77).map(|x| x as u8).collect();
+
+	let mut encrypted = Vec::new();
+	assert_eq!(encrypt_sync(&mut &data[..], &key, &mut encrypted).unwrap(), data.len());
+	let mut decrypted = Vec::new();
+	assert_eq!(decrypt_sync(&mut &encrypted[..], &key, &mut decrypted).unwrap(), encrypted.len());
+
+	assert_eq!(decrypted, data);
+	assert!(decrypt_sync(&mut &encrypted[..encrypted.len()-1], &key, &mut Vec::new()).is_err());
+    }
+
+    #[test]
+    fn stream_round_trip_padding_sync()
+    {
+	let key = RsaPrivateKey::generate().unwrap();
+	let data: Vec<u8> = (0..(consts::RSA_SIG_SIZE - PADDING_NEEDS) * 3 + 77).map(|x| x as u8).collect();
+
+	for &padding in [Padding::PKCS1, Padding::PKCS1_OAEP].iter() {
+	    let mut encrypted = Vec::new();
+	    assert_eq!(encrypt_sync_with(&mut &data[..], &key, &mut encrypted, padding).unwrap(), data.len());
+	    let mut decrypted = Vec::new();
+	    assert_eq!(decrypt_sync_with(&mut &encrypted[..], &key, &mut decrypted, padding).unwrap(), encrypted.len());
+	    assert_eq!(decrypted, data);
+	}
+
+	let mut encrypted = Vec::new();
+	encrypt_sync_with(&mut &data[..], &key, &mut encrypted, Padding::PKCS1_OAEP).unwrap();
+	assert!(matches!(decrypt_sync(&mut &encrypted[..], &key, &mut Vec::new()), Err(Error::Decrypt)));
+	assert!(matches!(encrypt_sync_with(&mut &data[..], &key, &mut Vec::new(), Padding::PKCS1_PSS), Err(Error::Encrypt)));
+	assert!(matches!(decrypt_sync_with(&mut &encrypted[..], &key, &mut Vec::new(), Padding::NONE), Err(Error::Decrypt)));
+    }
+
+    #[test]
+    fn slice_too_large()
+    {
+	let key = RsaPrivateKey::generate().unwrap();
+	let max = consts::RSA_SIG_SIZE - PADDING_NEEDS;
+	let data = vec![0xa5u8; max + 1];
+
+	assert!(matches!(encrypt_slice_to_vec(&data[..], &key), Err(Error::TooLarge{max: m, got}) if m == max && got == max + 1));
+	assert!(matches!(encrypt_slice_sync(&data[..], &key, &mut Vec::new()), Err(Error::TooLarge{..})));
+
+	let encrypted = encrypt_slice_to_vec(&data[..max], &key).unwrap();
+	assert_eq!(decrypt_slice_to_vec(&encrypted[..], &key).unwrap(), &data[..max]);
+
+	// OAEP takes up more of the block than PKCS1
+	let max = consts::RSA_SIG_SIZE - OAEP_PADDING_NEEDS;
+	assert!(matches!(encrypt_slice_to_vec_with(&data[..max + 1], &key, Padding::PKCS1_OAEP), Err(Error::TooLarge{max: m, got}) if m == max && got == max + 1));
+	assert!(matches!(encrypt_slice_sync_with(&data[..max + 1], &key, &mut Vec::new(), Padding::PKCS1_OAEP), Err(Error::TooLarge{..})));
+    }
+
+    #[test]
+    fn slice_padding()
+    {
+	let key = RsaPrivateKey::generate().unwrap();
+	let data = b"some data to encrypt";
+
+	for &padding in [Padding::PKCS1, Padding::PKCS1_OAEP].iter() {
+	    let encrypted = encrypt_slice_to_vec_with(&data[..], &key, padding).unwrap();
+	    assert_eq!(decrypt_slice_to_vec_with(&encrypted[..], &key, padding).unwrap(), &data[..]);
+	}
+
+	let encrypted = encrypt_slice_to_vec_with(&data[..], &key, Padding::PKCS1_OAEP).unwrap();
+	assert!(matches!(decrypt_slice_to_vec(&encrypted[..], &key), Err(Error::Decrypt)));
+	assert!(matches!(encrypt_slice_to_vec_with(&data[..], &key, Padding::NONE), Err(Error::Encrypt)));
+	assert!(matches!(decrypt_slice_to_vec_with(&encrypted[..], &key, Padding::PKCS1_PSS), Err(Error::Decrypt)));
+    }
+
+    #[cfg(feature="async")]
+    #[tokio::test]
+    async fn stream_round_trip()
+    {
+	let key = RsaPrivateKey::generate().unwrap();
+	let data: Vec<u8> = (0..(consts::RSA_SIG_SIZE - PADDING_NEEDS) * 2 + 5).map(|x| x as u8).collect();
+
+	let mut encrypted = Vec::new();
+	assert_eq!(encrypt(&mut &data[..], &key, &mut encrypted).await.unwrap(), data.len());
+	let mut decrypted = Vec::new();
+	decrypt(&mut &encrypted[..], &key, &mut decrypted).await.unwrap();
+
+	assert_eq!(decrypted, data);
+    }
+
+    #[cfg(feature="async")]
+    #[tokio::test]
+    async fn stream_round_trip_padding()
+    {
+	let key = RsaPrivateKey::generate().unwrap();
+	let data: Vec<u8> = (0..(consts::RSA_SIG_SIZE - PADDING_NEEDS) * 2 + 5).map(|x| x as u8).collect();
+
+	for &padding in [Padding::PKCS1, Padding::PKCS1_OAEP].iter() {
+	    let mut encrypted = Vec::new();
+	    assert_eq!(encrypt_with(&mut &data[..], &key, &mut encrypted, padding).await.unwrap(), data.len());
+	    let mut decrypted = Vec::new();
+	    decrypt_with(&mut &encrypted[..], &key, &mut decrypted, padding).await.unwrap();
+	    assert_eq!(decrypted, data);
+
+	    let mut encrypted = Vec::new();
+	    encrypt_slice_with(&data[..50], &key, &mut encrypted, padding).await.unwrap();
+	    let mut decrypted = Vec::new();
+	    decrypt_slice_with(&encrypted[..], &key, &mut decrypted, padding).await.unwrap();
+	    assert_eq!(&decrypted[..], &data[..50]);
+	}
+    }
+}
@@ -0,0 +1,90 @@
+//! A public key followed by a payload in one stream
+//!
+//! The envelope is the binary representation of an `RsaPublicKey` (see `RsaPublicKey::to_bytes()`), then the payload length as a little-endian `u64`, then the payload itself.
+use super::*;
+use std::{
+    io::{
+	self,
+	Read,
+	Write,
+    },
+    convert::TryFrom,
+};
+
+/// Size of the payload length prefix
+const LEN_SIZE: usize = std::mem::size_of::<u64>();
+
+/// Write `key` followed by the length-prefixed `payload` to a stream. Returns the number of bytes written.
+pub fn write_sync<T>(to: &mut T, key: &RsaPublicKey, payload: impl AsRef<[u8]>) -> io::Result<usize>
+where T: Write + ?Sized
+{
+    let payload = payload.as_ref();
+    let done = key.write_to_sync(to)?;
+
+    let len = u64::try_from(payload.len()).map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "payload too large"))?;
+    to.write_all(&len.to_le_bytes())?;
+    to.write_all(payload)?;
+
+    Ok(done + LEN_SIZE + payload.len())
+}
+
+/// Read a key and its length-prefixed payload written by `write_sync()` from a stream.
+pub fn read_sync<T>(from: &mut T) -> io::Result<(RsaPublicKey, Vec<u8>)>
+where T: Read + ?Sized
+{
+    let key = RsaPublicKey::read_sync(from)?;
+
+    let mut len = [0u8; LEN_SIZE];
+    from.read_exact(&mut len[..])?;
+    let len = u64::from_le_bytes(len);
+
+    // Don't trust the length enough to allocate it all up front
+    let mut payload = Vec::new();
+    from.take(len).read_to_end(&mut payload)?;
+    if payload.len() as u64 != len {
+	return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "couldn't read payload"));
+    }
+
+    Ok((key, payload))
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn round_trip()
+    {
+	let key = RsaPrivateKey::generate().unwrap().get_public_parts();
+	let payload = b"some payload that follows the key";
+
+	let mut buffer = Vec::new();
+	let written = write_sync(&mut buffer, &key, &payload[..]).unwrap();
+	write_sync(&mut buffer, &key, []).unwrap();
+	assert_eq!(written, key.to_bytes().len() + LEN_SIZE + payload.len());
+
+	let mut reader = &buffer[..];
+	let (rkey, rpayload) = read_sync(&mut reader).unwrap();
+	assert_eq!(rkey, key);
+	assert_eq!(&rpayload[..], &payload[..]);
+
+	let (rkey, rpayload) = read_sync(&mut reader).unwrap();
+	assert_eq!(rkey, key);
+	assert!(rpayload.is_empty());
+	assert!(reader.is_empty());
+
+	assert!(read_sync(&mut &buffer[..written-1]).is_err());
+    }
+
+    #[test]
+    fn huge_key_lengths()
+    {
+	// The key header claims a body far larger than the stream, which must not be allocated up front
+	let huge = usize::MAX / 4;
+	let mut buffer = Vec::from(crate::bytes::refer(&offsets::PublicOffsetGroup { n: huge, e: huge }));
+	buffer.extend_from_slice(b"not nearly enough");
+
+	assert_eq!(read_sync(&mut &buffer[..]).unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+    }
+}
@@ -0,0 +1,162 @@
+//! Signed and encrypted messages
+//!
+//! A message is the sender's signature, then the data, sealed with a random AES key (see `aes::seal()`) that is wrapped for the recipient with `RsaPublicKey::wrap_key()`.
+//! The signature covers the SHA256 hash of the recipient's public key before the data, so a recipient can't pass a message on to someone else as if the sender had sent it to them.
+//! The blob is the wrapped key, then the sealed frame. The wrapped key is also authenticated as the frame's additional data.
+use super::*;
+use openssl::{
+    hash::MessageDigest,
+    pkey::HasPrivate,
+    sign::{
+	Signer,
+	Verifier,
+    },
+};
+
+/// The hash of `recipient` that the sender's signature covers
+#[inline] fn recipient_hash(recipient: &RsaPublicKey) -> sha256::Sha256Hash
+{
+    sha256::compute_slice(recipient.to_bytes())
+}
+
+/// Wrap a random AES key for `their_pub` and seal `frame` with it
+fn seal_frame(frame: &[u8], their_pub: &RsaPublicKey) -> Result<Vec<u8>, Error>
+{
+    let session = aes::AesKey::generate().map_err(|_| Error::Encrypt)?;
+    let mut output = their_pub.wrap_key(&session)?;
+    let sealed = aes::seal(&session, frame, &output[..]).map_err(|_| Error::Encrypt)?;
+    output.extend_from_slice(&sealed[..]);
+    Ok(output)
+}
+
+/// Unwrap the AES key with `my_priv` and open the frame sealed with it
+fn open_frame(blob: &[u8], my_priv: &RsaPrivateKey) -> Result<Vec<u8>, Error>
+{
+    let wrapped_len = my_priv.size_bytes();
+    if blob.len() < wrapped_len {
+	return Err(Error::Binary(BinaryErrorKind::Length{expected: Some(wrapped_len), got: Some(blob.len())}));
+    }
+    let (wrapped, sealed) = blob.split_at(wrapped_len);
+
+    let session = my_priv.unwrap_key(wrapped)?;
+    aes::open(&session, sealed, wrapped).map_err(|_| Error::Decrypt)
+}
+
+/// Sign `data` with `my_priv`, then encrypt the signature and data so only the holder of `their_pub`'s private key can read them.
+///
+/// Use `decrypt_and_verify()` to get the data back.
+pub fn sign_and_encrypt<T,K>(data: T, my_priv: &K, their_pub: &RsaPublicKey) -> Result<Vec<u8>, Error>
+where T: AsRef<[u8]>,
+      K: PrivateKey + ?Sized,
+<K as PublicKey>::KeyType: HasPrivate
+{
+    let data = data.as_ref();
+    let pkey = my_priv.get_pkey_priv().map_err(|_| Error::Key)?;
+
+    let mut signer = Signer::new(MessageDigest::sha256(), &pkey)?;
+    signer.update(recipient_hash(their_pub).as_ref())?;
+    signer.update(data)?;
+
+    let mut frame = Vec::with_capacity(pkey.size() + data.len());
+    frame.extend_from_slice(&signer.sign_to_vec()?[..]);
+    frame.extend_from_slice(data);
+
+    seal_frame(&frame[..], their_pub)
+}
+
+/// Decrypt a message made by `sign_and_encrypt()` with `my_priv`, and verify it was signed by `their_pub` for `my_priv`. Returns the data only if both succeed.
+///
+/// # Errors
+/// `Error::Decrypt` if the message was not encrypted for `my_priv` or has been modified, and `Error::Verify` if it was not signed by `their_pub`, or was signed for someone else.
+pub fn decrypt_and_verify<T,K>(blob: T, my_priv: &RsaPrivateKey, their_pub: &K) -> Result<Vec<u8>, Error>
+where T: AsRef<[u8]>,
+      K: PublicKey + ?Sized
+{
+    let mut frame = open_frame(blob.as_ref(), my_priv)?;
+
+    let pkey = their_pub.get_pkey_pub().map_err(|_| Error::Key)?;
+    let signature_len = pkey.size();
+    if frame.len() < signature_len {
+	return Err(Error::Binary(BinaryErrorKind::Length{expected: Some(signature_len), got: Some(frame.len())}));
+    }
+
+    let mut veri = Verifier::new(MessageDigest::sha256(), &pkey)?;
+    veri.update(recipient_hash(&my_priv.get_public_parts()).as_ref())?;
+    veri.update(&frame[signature_len..])?;
+    if !veri.verify(&frame[..signature_len])? {
+	return Err(Error::Verify);
+    }
+    frame.drain(..signature_len);
+    Ok(frame)
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn round_trip()
+    {
+	let sender = RsaPrivateKey::generate().unwrap();
+	let recipient = RsaPrivateKey::generate().unwrap();
+	let data = b"a message only the recipient can read, from the sender";
+
+	let blob = sign_and_encrypt(&data[..], &sender, &recipient.get_public_parts()).unwrap();
+	assert_eq!(decrypt_and_verify(&blob, &recipient, &sender.get_public_parts()).unwrap(), &data[..]);
+	assert_ne!(sign_and_encrypt(&data[..], &sender, &recipient.get_public_parts()).unwrap(), blob);
+
+	let empty = sign_and_encrypt([], &sender, &recipient.get_public_parts()).unwrap();
+	assert!(decrypt_and_verify(&empty, &recipient, &sender).unwrap().is_empty());
+    }
+
+    #[test]
+    fn tampered()
+    {
+	let sender = RsaPrivateKey::generate().unwrap();
+	let recipient = RsaPrivateKey::generate().unwrap();
+	let data = b"a message only the recipient can read, from the sender";
+	let blob = sign_and_encrypt(&data[..], &sender, &recipient.get_public_parts()).unwrap();
+
+	let mut flipped = blob.clone();
+	*flipped.last_mut().unwrap() ^= 1;
+	assert!(matches!(decrypt_and_verify(&flipped, &recipient, &sender), Err(Error::Decrypt)));
+	let mut flipped = blob.clone();
+	flipped[recipient.size_bytes() + 20] ^= 1;
+	assert!(matches!(decrypt_and_verify(&flipped, &recipient, &sender), Err(Error::Decrypt)));
+
+	// Encrypted for the recipient, but signed by someone else
+	let impostor = RsaPrivateKey::generate().unwrap();
+	let forged = sign_and_encrypt(&data[..], &impostor, &recipient.get_public_parts()).unwrap();
+	assert!(matches!(decrypt_and_verify(&forged, &recipient, &sender), Err(Error::Verify)));
+
+	assert!(matches!(decrypt_and_verify(&blob, &impostor, &sender), Err(Error::Decrypt)));
+	assert!(matches!(decrypt_and_verify(&blob[..10], &recipient, &sender), Err(Error::Binary(_))));
+    }
+
+    #[test]
+    fn forwarded()
+    {
+	let sender = RsaPrivateKey::generate().unwrap();
+	let recipient = RsaPrivateKey::generate().unwrap();
+	let third = RsaPrivateKey::generate().unwrap();
+	let data = b"a message only the recipient can read, from the sender";
+	let blob = sign_and_encrypt(&data[..], &sender, &recipient.get_public_parts()).unwrap();
+
+	// The recipient re-encrypts the signed frame for someone else
+	let frame = open_frame(&blob[..], &recipient).unwrap();
+	let forwarded = seal_frame(&frame[..], &third.get_public_parts()).unwrap();
+	assert!(matches!(decrypt_and_verify(&forwarded, &third, &sender), Err(Error::Verify)));
+    }
+
+    #[test]
+    fn key_sizes()
+    {
+	let sender: RsaPrivateKey = openssl::rsa::Rsa::generate(2048).unwrap().into();
+	let recipient: RsaPrivateKey = openssl::rsa::Rsa::generate(3072).unwrap().into();
+	let data = b"a message between keys of other sizes";
+
+	let blob = sign_and_encrypt(&data[..], &sender, &recipient.get_public_parts()).unwrap();
+	assert_eq!(decrypt_and_verify(&blob, &recipient, &sender).unwrap(), &data[..]);
+    }
+}
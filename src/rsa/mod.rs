@@ -36,4 +36,18 @@ pub use sign::*;
 mod crypt;
 pub use crypt::*;
 
+#[cfg(feature="aes")]
+mod message;
+#[cfg(feature="aes")]
+pub use message::*;
+
+pub mod envelope;
+
+#[cfg(feature="jwk")]
+mod jwk;
+
+#[cfg(feature="x509")]
+mod x509;
+
 pub use crate::error::rsa::*;
+pub use crate::error::rsa::Error;
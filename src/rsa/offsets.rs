@@ -7,7 +7,8 @@ pub struct Starts<T: HasOffsets>(T);
 pub trait HasOffsets: Sized
 {
     fn starts(&self) -> Starts<Self>;
-    fn body_len(&self) -> usize;
+    /// The total length of the components, or `None` if it overflows `usize`
+    fn body_len(&self) -> Option<usize>;
 }
 pub trait HasPublicOffsets: HasOffsets
 {
@@ -109,3 +110,26 @@ impl<T> Debug for Starts<T>
 	write!(f, "Starts ({:?})", self.0)
     }
 }
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use crate::bytes::*;
+    use std::mem;
+    #[test]
+    fn read_struct_offsets()
+    {
+	let public = PublicOffsetGroup { n: 256, e: 3 };
+	let private = PrivateOffsetGroup { n: 256, e: 3, d: 256, p: 128, q: 128, dmp1: 128, dmq1: 128, iqmp: 128 };
+
+	let mut buffer = vec![0u8; mem::size_of::<PrivateOffsetGroup>() + 1];
+	copy_slice(&mut buffer[1..], refer(&public));
+	assert_eq!(read_struct::<PublicOffsetGroup>(&buffer[1..]), Some(public));
+	assert_eq!(read_struct::<PublicOffsetGroup>(&buffer[1..mem::size_of::<PublicOffsetGroup>()]), None);
+
+	copy_slice(&mut buffer[1..], refer(&private));
+	assert_eq!(read_struct::<PrivateOffsetGroup>(&buffer[1..]), Some(private));
+	assert_eq!(read_struct::<PrivateOffsetGroup>(&buffer[2..]), None);
+    }
+}
@@ -4,6 +4,7 @@ use offsets::*;
 use crate::password::{
     Password,
 };
+use zeroize::Zeroizing;
 #[allow(unused_imports)]
 use std::{
     borrow::{
@@ -22,6 +23,7 @@ use std::{
     convert::{
 	TryFrom,
     },
+    str::FromStr,
 };
 use openssl::{
     bn::BigNumRef,
@@ -49,8 +51,7 @@ use tokio::io::{
 /// # Notes
 /// It is always assumed that the internal consistancy and state of the components binary representations is correct.
 /// Incorrect internal state can cause panics on all operations.
-#[derive(Clone, PartialEq, Eq, Hash, Debug)]
-#[cfg_attr(feature="serialise", derive(Serialize,Deserialize))]
+#[derive(Clone, PartialEq, Eq, Hash)]
 pub struct RsaPrivateKey
 {
     data: Vec<u8>,
@@ -58,6 +59,68 @@ pub struct RsaPrivateKey
     offset: PrivateOffsetGroup,
 }
 
+impl fmt::Debug for RsaPrivateKey
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+	f.debug_struct("RsaPrivateKey")
+	    .field("offset", &self.offset)
+	    .finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature="serialise")] const _: () = {
+    use serde::{
+	Serialize,
+    };
+
+    impl Serialize for RsaPrivateKey
+    {
+	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+	where
+            S: serde::ser::Serializer,
+	{
+            serializer.serialize_bytes(&self.to_bytes()[..])
+	}
+    }
+
+    pub struct RsaPrivateKeyVisitor;
+
+    impl<'de> serde::de::Visitor<'de> for RsaPrivateKeyVisitor {
+	type Value = RsaPrivateKey;
+
+	fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("the binary representation of an RSA private key")
+	}
+
+	fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E>
+	where E: serde::de::Error
+	{
+	    RsaPrivateKey::from_bytes(v).map_err(E::custom)
+	}
+	fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error> where
+	    A: serde::de::SeqAccess<'de>
+	{
+	    let mut bytes = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(4096));
+	    while let Some(byte) = seq.next_element()?
+	    {
+		bytes.push(byte);
+	    }
+	    use serde::de::Error;
+	    RsaPrivateKey::from_bytes(bytes).map_err(A::Error::custom)
+	}
+    }
+    impl<'de> serde::Deserialize<'de> for RsaPrivateKey {
+	fn deserialize<D>(deserializer: D) -> Result<RsaPrivateKey, D::Error>
+	where
+            D: serde::de::Deserializer<'de>,
+	{
+            deserializer.deserialize_bytes(RsaPrivateKeyVisitor)
+	}
+    }
+
+};
+
 impl fmt::Display for RsaPrivateKey
 {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
@@ -69,12 +132,34 @@ impl fmt::Display for RsaPrivateKey
 impl RsaPrivateKey
 {
     /// Generate a new RSA private key
-    pub fn generate() -> Result<Self, Error>
+    #[inline] pub fn generate() -> Result<Self, Error>
+    {
+	Self::generate_with_bits(RSA_KEY_BITS)
+    }
+
+    /// Generate a new RSA private key with a modulus of `bits` bits
+    ///
+    /// # Notes
+    /// Signing with `sign_slice()` and friends needs a key of `RSA_KEY_BITS` bits, since signatures are a fixed `RSA_SIG_SIZE` bytes.
+    pub fn generate_with_bits(bits: u32) -> Result<Self, Error>
+    {
+	Ok(Rsa::generate(bits)?.into())
+    }
+
+    /// Generate a new RSA private key on Tokio's blocking thread pool
+    ///
+    /// # Notes
+    /// Key generation is slow and CPU-bound, this avoids stalling the runtime while it happens.
+    /// Must be called from within a Tokio runtime.
+    #[cfg(feature="async")] 
+    pub async fn generate_async() -> Result<Self, Error>
     {
-	Ok(Rsa::generate(RSA_KEY_BITS)?.into())
+	tokio::task::spawn_blocking(Self::generate).await
+	    .map_err(|_| Error::Unknown)?
     }
     
     /// Create a new private key from its components
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
 	n: impl Borrow<BigNumRef>,
 	e: impl Borrow<BigNumRef>,
@@ -131,6 +216,22 @@ impl RsaPrivateKey
 	)?)
     }
     
+    /// Check if this instance holds the same key as `other`.
+    ///
+    /// # Notes
+    /// Unlike `==`, this compares the numeric values of the components rather than their binary representations. Two instances of the same key can compare unequal with `==` if their components were encoded differently (e.g. with leading zeros).
+    pub fn same_key(&self, other: &Self) -> bool
+    {
+	self.num_n() == other.num_n()
+	    && self.num_e() == other.num_e()
+	    && self.num_d() == other.num_d()
+	    && self.num_p() == other.num_p()
+	    && self.num_q() == other.num_q()
+	    && self.num_dmp1() == other.num_dmp1()
+	    && self.num_dmq1() == other.num_dmq1()
+	    && self.num_iqmp() == other.num_iqmp()
+    }
+
     /// Try to get the RSA public key from this instance of private key
     pub fn get_rsa_pub(&self) -> Result<Rsa<Public>, Error>
     {
@@ -150,13 +251,42 @@ impl RsaPrivateKey
 	)
     }
 
-    /// Create a PEM string from this instance
-    pub fn to_pem(&self, pw: Option<&Password>) -> Result<String, Error>
+    /// Decrypt an AES key and IV wrapped with `RsaPublicKey::wrap_key()`
+    ///
+    /// # Errors
+    /// `Error::Decrypt` if it was not wrapped for this key, and `Error::Binary` if it does not decrypt to exactly the size of an `AesKey`.
+    #[cfg(feature="aes")]
+    pub fn unwrap_key(&self, wrapped: impl AsRef<[u8]>) -> Result<aes::AesKey, Error>
+    {
+	const SIZE: usize = consts::AES_KEYSIZE + consts::AES_IVSIZE;
+
+	let rsa = self.get_rsa_priv()?;
+	let mut decrypted = Zeroizing::new(vec![0u8; usize::try_from(rsa.size())?]);
+	let len = rsa.private_decrypt(wrapped.as_ref(), &mut decrypted[..], openssl::rsa::Padding::PKCS1_OAEP).map_err(|_| Error::Decrypt)?;
+	if len != SIZE {
+	    return Err(Error::Binary(BinaryErrorKind::Length{expected: Some(SIZE), got: Some(len)}));
+	}
+	let mut key = Zeroizing::new([0u8; SIZE]);
+	bytes::copy_slice(&mut key[..], &decrypted[..len]);
+	Ok(aes::AesKey::from_bytes(*key))
+    }
+
+    /// Create a PEM string from this instance, encrypted with AES-128-CBC if a password is given
+    #[inline] pub fn to_pem(&self, pw: Option<&Password>) -> Result<String, Error>
+    {
+	self.to_pem_with(pw, Cipher::aes_128_cbc())
+    }
+
+    /// Create a PEM string from this instance, encrypted with `cipher` if a password is given
+    ///
+    /// # Notes
+    /// `from_pem()` reads the cipher from the PEM header, so it does not need to be told which one was used.
+    pub fn to_pem_with(&self, pw: Option<&Password>, cipher: Cipher) -> Result<String, Error>
     {
 	let rsa = self.get_rsa_priv()?;
 	Ok(std::str::from_utf8(&match pw {
 	    Some(password) => {
-		rsa.private_key_to_pem_passphrase(Cipher::aes_128_cbc(), password.as_ref())?
+		rsa.private_key_to_pem_passphrase(cipher, password.as_ref())?
 	    },
 	    None => {
 		rsa.private_key_to_pem()?
@@ -178,6 +308,21 @@ impl RsaPrivateKey
 	})?.into())
     }
 
+    /// The named components of this key, the public `n` and `e` then `d`, `p`, `q`, `dmp1`, `dmq1` and `iqmp`, as big-endian bytes
+    pub fn components(&self) -> impl Iterator<Item = (&'static str, &[u8])> + '_
+    {
+	IntoIterator::into_iter([
+	    ("n", self.n()),
+	    ("e", self.e()),
+	    ("d", self.d()),
+	    ("p", self.p()),
+	    ("q", self.q()),
+	    ("dmp1", self.dmp1()),
+	    ("dmq1", self.dmq1()),
+	    ("iqmp", self.iqmp()),
+	])
+    }
+
     /// Validates the RSA key parameters for correctness
     pub fn check_key(&self) -> bool
     {
@@ -192,23 +337,18 @@ impl RsaPrivateKey
 	const OFF_SIZE: usize = size_of::<PrivateOffsetGroup>();
 	let bytes = bytes.as_ref();
 
-	if bytes.len() < OFF_SIZE {
-	    return Err(Error::Binary(BinaryErrorKind::Length{expected: Some(OFF_SIZE), got: Some(bytes.len())}));
-	}
-
-	let offset = unsafe{
-	    bytes::derefer_unchecked::<PrivateOffsetGroup>(&bytes[..OFF_SIZE])
-		.read_unaligned()
-	};
+	// The input is not necessarily aligned for the offsets, so they are copied out rather than referenced
+	let offset: PrivateOffsetGroup = bytes::read_struct(bytes)
+	    .ok_or(Error::Binary(BinaryErrorKind::Length{expected: Some(OFF_SIZE), got: Some(bytes.len())}))?;
 	let bytes = &bytes[OFF_SIZE..];
-	let sz = offset.body_len();
+	let sz = offset.body_len().ok_or(Error::Binary(BinaryErrorKind::Corruption))?;
 
 	if bytes.len() < sz {
 	    return Err(Error::Binary(BinaryErrorKind::Length{expected: Some(sz), got: Some(bytes.len())}));
 	}
 
 	Ok(Self{
-	    data: Vec::from(&bytes[..]),
+	    data: Vec::from(bytes),
 	    offset_starts: offset.starts(),
 	    offset,
 	})
@@ -221,12 +361,40 @@ impl RsaPrivateKey
 	self.write_to_sync(&mut output).unwrap();
 	output
     }
+
+    /// Write the binary representation of this instance to a new `Vec<u8>` that is zeroed when dropped
+    ///
+    /// # Notes
+    /// The vector is allocated at its full size up front, so no partial copies are left behind by it growing.
+    pub fn to_bytes_zeroizing(&self) -> Zeroizing<Vec<u8>>
+    {
+	let mut output = Zeroizing::new(Vec::with_capacity(self.serialized_len()));
+	self.write_to_sync(&mut *output).unwrap();
+	output
+    }
     
     /// Return the length of the data body only (not including header).
+    ///
+    /// # Notes
+    /// Use `serialized_len()` for the size of the full binary representation.
     #[inline] pub fn len(&self) -> usize
     {
 	self.data.len()
     }
+
+    /// Is the data body empty
+    #[inline] pub fn is_empty(&self) -> bool
+    {
+	self.data.is_empty()
+    }
+
+    /// Return the length of the full binary representation, including the header.
+    ///
+    /// This is the number of bytes `to_bytes()` returns and `write_to_sync()` writes.
+    #[inline] pub fn serialized_len(&self) -> usize
+    {
+	size_of::<PrivateOffsetGroup>() + self.data.len()
+    }
     
     /// Write this private key as bytes to a stream
     #[cfg(feature="async")]
@@ -256,26 +424,19 @@ impl RsaPrivateKey
 	const OFF_SIZE: usize = size_of::<PrivateOffsetGroup>();
 	
 	let offset: PrivateOffsetGroup = {
-	    union BufHack {
-		buffer: [u8; OFF_SIZE],
-		res: PrivateOffsetGroup,
-	    }
-	    let mut offsets = BufHack{buffer: [0u8; OFF_SIZE]};
-	    unsafe {
-		let buffer = &mut offsets.buffer;
-
-		if buffer.len() != from.read_exact(&mut buffer[..]).await? {
-		    return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "couldn't read offsets"));
-		}
-	    }
-	    unsafe{
-		offsets.res
+	    let mut buffer = [0u8; OFF_SIZE];
+	    if buffer.len() != from.read_exact(&mut buffer[..]).await? {
+		return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "couldn't read offsets"));
 	    }
+	    bytes::read_struct(&buffer[..]).expect("buffer is the size of the offsets")
 	};
 
-	let mut data = vec![0u8; offset.body_len()];
+	let len = offset.body_len().ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "component lengths overflow"))?;
 
-	if from.read_exact(&mut data[..]).await? != data.len() {
+	// Don't trust the lengths enough to allocate them all up front
+	let mut data = Vec::new();
+	(&mut *from).take(len as u64).read_to_end(&mut data).await?;
+	if data.len() != len {
 	    return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "couldn't read data body"));
 	}
 
@@ -293,23 +454,19 @@ impl RsaPrivateKey
 	const OFF_SIZE: usize = size_of::<PrivateOffsetGroup>();
 	
 	let offset: PrivateOffsetGroup = {
-	    union BufHack {
-		buffer: [u8; OFF_SIZE],
-		res: PrivateOffsetGroup,
-	    }
-	    let mut offsets = BufHack{buffer: [0u8; OFF_SIZE]};
-	    unsafe {
-		let buffer = &mut offsets.buffer;
-		from.read_exact(&mut buffer[..])?;
-	    }
-	    unsafe {
-		offsets.res
-	    }
+	    let mut buffer = [0u8; OFF_SIZE];
+	    from.read_exact(&mut buffer[..])?;
+	    bytes::read_struct(&buffer[..]).expect("buffer is the size of the offsets")
 	};
 
-	let mut data = vec![0u8; offset.body_len()];
+	let len = offset.body_len().ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "component lengths overflow"))?;
 
-	from.read_exact(&mut data[..])?;
+	// Don't trust the lengths enough to allocate them all up front
+	let mut data = Vec::new();
+	from.take(len as u64).read_to_end(&mut data)?;
+	if data.len() != len {
+	    return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "couldn't read data body"));
+	}
 
 	Ok(Self {
 	    data,
@@ -324,7 +481,7 @@ impl HasComponents for RsaPrivateKey
 {
     fn raw(&self) -> &[u8]
     {
-	return &self.data[..]
+	&self.data[..]
     }
 }
 
@@ -401,6 +558,14 @@ impl From<RsaPrivateKey> for RsaPublicKey
     }
 }
 
+impl From<&RsaPrivateKey> for RsaPublicKey
+{
+    #[inline] fn from(from: &RsaPrivateKey) -> Self
+    {
+	from.get_public_parts()
+    }
+}
+
 impl PublicKey for RsaPrivateKey
 {
     type KeyType = Private;
@@ -418,6 +583,39 @@ impl PublicKey for RsaPrivateKey
 }
 impl PrivateKey for RsaPrivateKey{}
 
+/// Parses an unencrypted PEM string.
+///
+/// # Errors
+/// `Error::Password` if the PEM is encrypted, use `RsaPrivateKey::from_pem()` with a password for those.
+impl FromStr for RsaPrivateKey
+{
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err>
+    {
+	let mut needs_password = false;
+	Self::from_pem(s, || {
+	    needs_password = true;
+	    None
+	}).map_err(|err| if needs_password {
+	    Error::Password
+	} else {
+	    err
+	})
+    }
+}
+
+/// Parses an unencrypted PEM string, see `FromStr`.
+impl TryFrom<&str> for RsaPrivateKey
+{
+    type Error = Error;
+
+    #[inline] fn try_from(from: &str) -> Result<Self, Self::Error>
+    {
+	from.parse()
+    }
+}
+
 impl From<RsaPrivateKey> for Vec<u8>
 {
     #[inline] fn from(from: RsaPrivateKey) -> Self
@@ -435,3 +633,254 @@ impl TryFrom<Vec<u8>> for RsaPrivateKey
 	Self::from_bytes(from)
     }
 }
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn to_bytes_zeroizing()
+    {
+	let key = RsaPrivateKey::generate().unwrap();
+	let bytes: zeroize::Zeroizing<Vec<u8>> = key.to_bytes_zeroizing();
+
+	assert_eq!(&bytes[..], &key.to_bytes()[..]);
+	assert_eq!(bytes.capacity(), key.serialized_len());
+	assert_eq!(RsaPrivateKey::from_bytes(&bytes[..]).unwrap(), key);
+    }
+
+    #[test]
+    fn from_bytes_overflow()
+    {
+	// The lengths sum to exactly `usize::MAX + 1`, which would wrap to an empty body
+	let eighth = usize::MAX / 8 + 1;
+	let offsets = PrivateOffsetGroup { n: eighth, e: eighth, d: eighth, p: eighth, q: eighth, dmp1: eighth, dmq1: eighth, iqmp: eighth };
+	let bytes = Vec::from(bytes::refer(&offsets));
+
+	assert!(matches!(RsaPrivateKey::from_bytes(&bytes), Err(Error::Binary(BinaryErrorKind::Corruption))));
+	assert_eq!(RsaPrivateKey::generate().unwrap().read_from_sync(&mut &bytes[..]).unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn serialized_len()
+    {
+	let key = RsaPrivateKey::generate().unwrap();
+	assert_eq!(key.serialized_len(), key.to_bytes().len());
+	assert_eq!(key.serialized_len(), key.write_to_sync(&mut Vec::new()).unwrap());
+	assert_eq!(key.serialized_len() - key.len(), size_of::<PrivateOffsetGroup>());
+	assert!(!key.is_empty());
+    }
+
+    #[test]
+    fn from_bytes_unaligned()
+    {
+	let key = RsaPrivateKey::generate().unwrap();
+	let mut bytes = vec![0u8];
+	bytes.extend_from_slice(&key.to_bytes()[..]);
+
+	assert_eq!(RsaPrivateKey::from_bytes(&bytes[1..]).unwrap(), key);
+	assert!(matches!(RsaPrivateKey::from_bytes(&bytes[1..4]), Err(Error::Binary(BinaryErrorKind::Length{..}))));
+    }
+
+    #[test]
+    fn from_pem_encrypted()
+    {
+	let key = RsaPrivateKey::generate().unwrap();
+	let password = Password::derive("hello world", &crate::password::Salt::embedded());
+	let pem = key.to_pem(Some(&password)).unwrap();
+
+	assert_eq!(RsaPrivateKey::from_pem(&pem, || Some(password.clone())).unwrap(), key);
+	assert!(RsaPrivateKey::from_pem(&pem, || None).is_err());
+	assert!(RsaPrivateKey::from_pem(&pem, || Some(Password::derive("wrong", &crate::password::Salt::embedded()))).is_err());
+
+	// Unencrypted PEM never asks for a password
+	assert_eq!(RsaPrivateKey::from_pem(key.to_pem(None).unwrap(), || panic!("password requested")).unwrap(), key);
+    }
+
+    #[cfg(feature="aes")]
+    #[test]
+    fn wrap_key()
+    {
+	let key = RsaPrivateKey::generate().unwrap();
+	let session = crate::aes::AesKey::generate().unwrap();
+
+	let wrapped = key.get_public_parts().wrap_key(&session).unwrap();
+	assert_eq!(wrapped.len(), key.size_bytes());
+	assert_eq!(key.unwrap_key(&wrapped).unwrap(), session);
+
+	let other = RsaPrivateKey::generate().unwrap();
+	assert!(matches!(other.unwrap_key(&wrapped), Err(Error::Decrypt)));
+
+	let rsa = key.get_rsa_pub().unwrap();
+	let mut short = vec![0u8; key.size_bytes()];
+	rsa.public_encrypt(&session.as_ref()[1..], &mut short[..], openssl::rsa::Padding::PKCS1_OAEP).unwrap();
+	assert!(matches!(key.unwrap_key(&short), Err(Error::Binary(BinaryErrorKind::Length{expected: Some(32), got: Some(31)}))));
+    }
+
+    #[test]
+    fn public_from_borrowed()
+    {
+	fn public(key: impl Into<RsaPublicKey>) -> RsaPublicKey
+	{
+	    key.into()
+	}
+	let key = RsaPrivateKey::generate().unwrap();
+
+	let borrowed = public(&key);
+	assert_eq!(borrowed, key.get_public_parts());
+	assert_eq!(borrowed, public(key));
+    }
+
+    #[test]
+    fn components()
+    {
+	let key = RsaPrivateKey::generate().unwrap();
+	let components: Vec<_> = key.components().collect();
+	let names: Vec<_> = components.iter().map(|&(name, _)| name).collect();
+	assert_eq!(names, ["n", "e", "d", "p", "q", "dmp1", "dmq1", "iqmp"]);
+
+	let expected = [key.n(), key.e(), key.d(), key.p(), key.q(), key.dmp1(), key.dmq1(), key.iqmp()];
+	for ((_, bytes), expected) in components.iter().zip(expected.iter()) {
+	    assert_eq!(bytes, expected);
+	}
+
+	let public = key.get_public_parts();
+	assert!(public.components().eq(key.components().take(2)));
+    }
+
+    #[test]
+    fn generate()
+    {
+	let key = RsaPrivateKey::generate().unwrap();
+	assert!(key.check_key());
+	assert_eq!(key.modulus_bits(), RSA_KEY_BITS);
+
+	let key = RsaPrivateKey::generate_with_bits(2048).unwrap();
+	assert!(key.check_key());
+	assert_eq!(key.modulus_bits(), 2048);
+
+	assert_eq!(RsaPublicKey::generate().unwrap().modulus_bits(), RSA_KEY_BITS);
+    }
+
+    #[test]
+    fn to_pem_with_cipher()
+    {
+	let key = RsaPrivateKey::generate().unwrap();
+	let password = Password::derive("hello world", &crate::password::Salt::embedded());
+
+	let pem = key.to_pem_with(Some(&password), Cipher::aes_256_cbc()).unwrap();
+	assert!(pem.contains("DEK-Info: AES-256-CBC,"));
+	assert!(key.to_pem(Some(&password)).unwrap().contains("DEK-Info: AES-128-CBC,"));
+
+	assert_eq!(RsaPrivateKey::from_pem(&pem, || Some(password.clone())).unwrap(), key);
+	assert!(RsaPrivateKey::from_pem(&pem, || Some(Password::derive("wrong", &crate::password::Salt::embedded()))).is_err());
+	assert_eq!(key.to_pem_with(None, Cipher::aes_256_cbc()).unwrap(), key.to_pem(None).unwrap());
+    }
+
+    #[test]
+    fn from_str()
+    {
+	let key = RsaPrivateKey::generate().unwrap();
+
+	let pem = key.to_pem(None).unwrap();
+	assert_eq!(pem.parse::<RsaPrivateKey>().unwrap(), key);
+	assert_eq!(RsaPrivateKey::try_from(&pem[..]).unwrap(), key);
+	assert!(matches!("not a key".parse::<RsaPrivateKey>(), Err(Error::OpenSSLInternal(_))));
+
+	let password = Password::derive("hello world", &crate::password::Salt::embedded());
+	let encrypted = key.to_pem(Some(&password)).unwrap();
+	assert!(matches!(encrypted.parse::<RsaPrivateKey>(), Err(Error::Password)));
+	assert_eq!(RsaPrivateKey::from_pem(&encrypted, || Some(password)).unwrap(), key);
+    }
+
+    #[test]
+    fn modulus_size()
+    {
+	for &bits in [2048u32, 4096].iter() {
+	    let key = RsaPrivateKey::from(Rsa::generate(bits).unwrap());
+
+	    assert_eq!(key.modulus_bits(), bits);
+	    assert_eq!(key.size_bytes(), bits as usize / 8);
+	    assert_eq!(key.size_bytes(), Rsa::from(key).size() as usize);
+	}
+    }
+
+    #[test]
+    fn debug_redacted()
+    {
+	let key = RsaPrivateKey::generate().unwrap();
+	let debug = format!("{:?}", key);
+
+	assert!(debug.starts_with("RsaPrivateKey {"));
+	assert!(debug.ends_with(", .. }"));
+	for component in [key.d(), key.p(), key.q()].iter() {
+	    assert!(!debug.contains(&crate::bytes::to_hex(&component[..4])));
+	    assert!(!debug.contains(&format!("{:?}", &component[..4])[1..]));
+	}
+    }
+
+    #[test]
+    fn same_key()
+    {
+	let key = RsaPrivateKey::generate().unwrap();
+
+	let mut data = Vec::new();
+	data.extend_from_slice(key.n());
+	data.extend_from_slice(key.e());
+	data.extend_from_slice(&[0u8, 0u8]);
+	data.extend_from_slice(key.d());
+	data.extend_from_slice(&key.data[key.offset_starts.p()..]);
+	let offset = PrivateOffsetGroup {
+	    d: key.offset.d + 2,
+	    ..key.offset
+	};
+	let padded = RsaPrivateKey {
+	    data,
+	    offset_starts: offset.starts(),
+	    offset,
+	};
+
+	// The binary representations differ, so `==` does not consider these equal
+	assert_ne!(padded, key);
+	assert!(padded.same_key(&key));
+	assert!(padded.check_key());
+
+	assert!(!RsaPrivateKey::generate().unwrap().same_key(&key));
+    }
+
+    #[cfg(feature="async")]
+    #[tokio::test]
+    async fn generate_async()
+    {
+	let key = RsaPrivateKey::generate_async().await.unwrap();
+	assert!(key.check_key());
+
+	let data = b"some data to sign";
+	let signature = sign_slice(&data[..], &key).unwrap();
+	assert!(signature.verify_slice(&data[..], &key).unwrap());
+    }
+}
+
+#[cfg(feature="serialise")]
+#[cfg(test)]
+mod serde_tests
+{
+    #[test]
+    fn ser_de()
+    {
+	let pv = super::RsaPrivateKey::generate().expect("genkey");
+
+	let value = serde_cbor::to_vec(&pv).expect("ser");
+	let output: super::RsaPrivateKey = serde_cbor::from_slice(&value[..]).expect("de");
+
+	assert_eq!(output, pv);
+	assert!(output.check_key());
+    }
+    #[test]
+    fn de_invalid()
+    {
+	let value = serde_cbor::to_vec(&serde_cbor::Value::Bytes(vec![0u8; 3])).expect("ser");
+	assert!(serde_cbor::from_slice::<super::RsaPrivateKey>(&value[..]).is_err());
+    }
+}
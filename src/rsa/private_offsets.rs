@@ -16,6 +16,9 @@ pub struct PrivateOffsetGroup
     pub iqmp: usize,
 }
 
+impl crate::bytes::sealed::Sealed for PrivateOffsetGroup{}
+impl crate::bytes::Pod for PrivateOffsetGroup{}
+
 impl HasOffsets for PrivateOffsetGroup
 {
     fn starts(&self) -> Starts<Self>
@@ -32,9 +35,10 @@ impl HasOffsets for PrivateOffsetGroup
 	}.into()
     }
 
-    fn body_len(&self) -> usize
+    fn body_len(&self) -> Option<usize>
     {
-	self.n+self.e+self.d+self.p+self.q+self.dmp1+self.dmq1+self.iqmp
+	[self.e, self.d, self.p, self.q, self.dmp1, self.dmq1, self.iqmp].iter()
+	    .try_fold(self.n, |len, &component| len.checked_add(component))
     }
 }
 
@@ -18,6 +18,8 @@ use std::{
     convert::{
 	TryFrom,
     },
+    cmp::Ordering,
+    str::FromStr,
 };
 use openssl::{
     bn::{
@@ -65,9 +67,12 @@ impl fmt::Display for RsaPublicKey
 impl RsaPublicKey
 {
     /// Generate a new RSA public key (kinda useless, use `RsaPrivateKey::generate()`).
-    pub fn generate() -> Result<Self, Error>
+    ///
+    /// # Notes
+    /// The private key is generated and then discarded, so nothing can decrypt for or sign with this key.
+    #[inline] pub fn generate() -> Result<Self, Error>
     {
-	Ok(Rsa::generate(RSA_KEY_BITS)?.into())
+	Ok(RsaPrivateKey::generate()?.get_public_parts())
     }
     /// Create a new RSAPublicKey from components
     pub fn new(
@@ -111,6 +116,40 @@ impl RsaPublicKey
 	Ok(Rsa::public_key_from_pem(pem)?.into())
     }
 
+    /// Try to create instances from a string of one or more concatenated PEM blocks.
+    ///
+    /// # Errors
+    /// `Error::PEMBlock` with the index of the first block that is unterminated or is not a valid public key.
+    pub fn from_pem_many(pem: impl AsRef<str>) -> Result<Vec<Self>, Error>
+    {
+	const BEGIN: &str = "-----BEGIN";
+	const END: &str = "-----END";
+
+	let mut pem = pem.as_ref();
+	let mut keys = Vec::new();
+	while let Some(start) = pem.find(BEGIN) {
+	    let index = keys.len();
+	    let block = &pem[start..];
+	    // The block ends at the end of its `-----END ...-----` line
+	    let end = block.find(END)
+		.and_then(|end| block[end+END.len()..].find("-----").map(|close| end + END.len() + close + "-----".len()))
+		.ok_or(Error::PEMBlock(index))?;
+
+	    keys.push(Rsa::public_key_from_pem(&block.as_bytes()[..end]).map_err(|_| Error::PEMBlock(index))?.into());
+	    pem = &block[end..];
+	}
+	Ok(keys)
+    }
+
+    /// The named components of this key, `n` then `e`, as big-endian bytes
+    pub fn components(&self) -> impl Iterator<Item = (&'static str, &[u8])> + '_
+    {
+	IntoIterator::into_iter([
+	    ("n", self.n()),
+	    ("e", self.e()),
+	])
+    }
+
     /// Validates the RSA key parameters for correctness
     pub fn check_key(&self) -> bool
     {
@@ -119,6 +158,30 @@ impl RsaPublicKey
 	    .unwrap_or(false)
     }
 
+    /// Check if this instance holds the same key as `other`.
+    ///
+    /// # Notes
+    /// Unlike `==`, this compares the numeric values of the components rather than their binary representations. Two instances of the same key can compare unequal with `==` if their components were encoded differently (e.g. with leading zeros).
+    pub fn same_key(&self, other: &Self) -> bool
+    {
+	self.num_n() == other.num_n()
+	    && self.num_e() == other.num_e()
+    }
+
+    /// Encrypt an AES key and IV so only the holder of the matching private key can read it. Use `RsaPrivateKey::unwrap_key()` to get it back.
+    ///
+    /// # Notes
+    /// The key is encrypted with OAEP padding, not the PKCS1 padding `encrypt_slice()` uses.
+    #[cfg(feature="aes")]
+    pub fn wrap_key(&self, key: &aes::AesKey) -> Result<Vec<u8>, Error>
+    {
+	let rsa = self.get_rsa_pub()?;
+	let mut output = vec![0u8; usize::try_from(rsa.size())?];
+	let len = rsa.public_encrypt(key.as_ref(), &mut output[..], openssl::rsa::Padding::PKCS1_OAEP).map_err(|_| Error::Encrypt)?;
+	output.truncate(len);
+	Ok(output)
+    }
+
     /// Try to get the RSA public key from this instance
     pub fn get_rsa_pub(&self) -> Result<Rsa<Public>, Error>
     {
@@ -133,23 +196,18 @@ impl RsaPublicKey
     {
 	let bytes = bytes.as_ref();
 
-	if bytes.len() < size_of::<PublicOffsetGroup>() {
-	    return Err(Error::Binary(BinaryErrorKind::Length{expected: Some(size_of::<PublicOffsetGroup>()), got: Some(bytes.len())}));
-	}
-
-	let offset = unsafe {
-	    bytes::derefer_unchecked::<PublicOffsetGroup>(&bytes[..size_of::<PublicOffsetGroup>()])
-		.read_unaligned()
-	};
+	// The input is not necessarily aligned for the offsets, so they are copied out rather than referenced
+	let offset: PublicOffsetGroup = bytes::read_struct(bytes)
+	    .ok_or(Error::Binary(BinaryErrorKind::Length{expected: Some(size_of::<PublicOffsetGroup>()), got: Some(bytes.len())}))?;
 	let bytes = &bytes[size_of::<PublicOffsetGroup>()..];
 
-	let sz = offset.body_len();
+	let sz = offset.body_len().ok_or(Error::Binary(BinaryErrorKind::Corruption))?;
 	if bytes.len() < sz {
 	    return Err(Error::Binary(BinaryErrorKind::Length{expected:Some(sz), got: Some(bytes.len())}));
 	}
 
 	Ok(Self {
-	    data: Vec::from(&bytes[..]),
+	    data: Vec::from(bytes),
 	    offset_starts: offset.starts(),
 	    offset,
 	})
@@ -164,11 +222,28 @@ impl RsaPublicKey
     }
 
     /// Return the length of the data body only (not including header).
+    ///
+    /// # Notes
+    /// Use `serialized_len()` for the size of the full binary representation.
     #[inline] pub fn len(&self) -> usize
     {
 	self.data.len()
     }
 
+    /// Is the data body empty
+    #[inline] pub fn is_empty(&self) -> bool
+    {
+	self.data.is_empty()
+    }
+
+    /// Return the length of the full binary representation, including the header.
+    ///
+    /// This is the number of bytes `to_bytes()` returns and `write_to_sync()` writes.
+    #[inline] pub fn serialized_len(&self) -> usize
+    {
+	size_of::<PublicOffsetGroup>() + self.data.len()
+    }
+
     /// Write this public key as bytes to a stream
     #[cfg(feature="async")]
     pub async fn write_to<T>(&self, to: &mut T) -> io::Result<usize>
@@ -196,25 +271,19 @@ impl RsaPublicKey
     where T: AsyncRead + Unpin + ?Sized
     {
 	let offset: PublicOffsetGroup = {
-	    union BufHack {
-		buffer: [u8; size_of::<PublicOffsetGroup>()],
-		offsets: PublicOffsetGroup,
-	    }
-	    let mut offsets = BufHack{buffer: [0u8; size_of::<PublicOffsetGroup>()]};
-	    unsafe {
-		let buffer = &mut offsets.buffer;
-		if buffer.len() != from.read_exact(&mut buffer[..]).await? {
-		    return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "couldn't read offsets"));
-		}
-	    }
-	    unsafe {
-		offsets.offsets
+	    let mut buffer = [0u8; size_of::<PublicOffsetGroup>()];
+	    if buffer.len() != from.read_exact(&mut buffer[..]).await? {
+		return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "couldn't read offsets"));
 	    }
+	    bytes::read_struct(&buffer[..]).expect("buffer is the size of the offsets")
 	};
 
-	let mut data = vec![0u8; offset.body_len()];
+	let len = offset.body_len().ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "component lengths overflow"))?;
 
-	if from.read_exact(&mut data[..]).await? != data.len() {
+	// Don't trust the lengths enough to allocate them all up front
+	let mut data = Vec::new();
+	(&mut *from).take(len as u64).read_to_end(&mut data).await?;
+	if data.len() != len {
 	    return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "couldn't read data body"));
 	}
 
@@ -226,27 +295,30 @@ impl RsaPublicKey
     }
 
     /// Read a public key from a stream
-    pub fn read_from_sync<T>(&self, from: &mut T) -> io::Result<Self>
+    #[inline] pub fn read_from_sync<T>(&self, from: &mut T) -> io::Result<Self>
+    where T: Read + ?Sized
+    {
+	Self::read_sync(from)
+    }
+
+    /// Read a public key from a stream without needing an existing instance
+    pub(super) fn read_sync<T>(from: &mut T) -> io::Result<Self>
     where T: Read + ?Sized
     {
 	let offset: PublicOffsetGroup = {
-	    union BufHack {
-		buffer: [u8; size_of::<PublicOffsetGroup>()],
-		offsets: PublicOffsetGroup,
-	    }
-	    let mut offsets = BufHack{buffer: [0u8; size_of::<PublicOffsetGroup>()]};
-	    unsafe {
-		let buffer = &mut offsets.buffer;
-		from.read_exact(&mut buffer[..])?;
-	    }
-	    unsafe {
-		offsets.offsets
-	    }
+	    let mut buffer = [0u8; size_of::<PublicOffsetGroup>()];
+	    from.read_exact(&mut buffer[..])?;
+	    bytes::read_struct(&buffer[..]).expect("buffer is the size of the offsets")
 	};
 
-	let mut data = vec![0u8; offset.body_len()];
+	let len = offset.body_len().ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "component lengths overflow"))?;
 
-	from.read_exact(&mut data[..])?;
+	// Don't trust the lengths enough to allocate them all up front
+	let mut data = Vec::new();
+	from.take(len as u64).read_to_end(&mut data)?;
+	if data.len() != len {
+	    return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "couldn't read data body"));
+	}
 
 	Ok(Self {
 	    data,
@@ -256,6 +328,40 @@ impl RsaPublicKey
     }
 }
 
+/// Compare two big-endian unsigned integers by value
+fn cmp_be(a: &[u8], b: &[u8]) -> Ordering
+{
+    fn trim(x: &[u8]) -> &[u8]
+    {
+	&x[x.iter().position(|&b| b != 0).unwrap_or(x.len())..]
+    }
+    let (a, b) = (trim(a), trim(b));
+    a.len().cmp(&b.len()).then_with(|| a.cmp(b))
+}
+
+/// Orders keys by modulus, then by exponent.
+///
+/// # Notes
+/// This compares the numeric values of the components. Only instances that are also `==` are `Ordering::Equal`, two instances of the same key with differently encoded components (see `same_key()`) are ordered next to each other by their binary representations.
+impl Ord for RsaPublicKey
+{
+    fn cmp(&self, other: &Self) -> Ordering
+    {
+	cmp_be(self.n(), other.n())
+	    .then_with(|| cmp_be(self.e(), other.e()))
+	    .then_with(|| self.data.cmp(&other.data))
+	    .then_with(|| (self.offset.n, self.offset.e).cmp(&(other.offset.n, other.offset.e)))
+    }
+}
+
+impl PartialOrd for RsaPublicKey
+{
+    #[inline] fn partial_cmp(&self, other: &Self) -> Option<Ordering>
+    {
+	Some(self.cmp(other))
+    }
+}
+
 impl HasComponents for RsaPublicKey
 {
     fn raw(&self) -> &[u8]
@@ -328,6 +434,28 @@ impl HasPublicComponents for RsaPublicKey
     }
 }
 
+impl FromStr for RsaPublicKey
+{
+    type Err = Error;
+
+    /// Parse a PEM string, see `from_pem()`
+    #[inline] fn from_str(s: &str) -> Result<Self, Self::Err>
+    {
+	Self::from_pem(s)
+    }
+}
+
+impl TryFrom<&str> for RsaPublicKey
+{
+    type Error = Error;
+
+    /// Parse a PEM string, see `from_pem()`
+    #[inline] fn try_from(from: &str) -> Result<Self, Self::Error>
+    {
+	Self::from_pem(from)
+    }
+}
+
 impl From<RsaPublicKey> for Vec<u8>
 {
     fn from(key: RsaPublicKey) -> Self
@@ -349,3 +477,145 @@ impl TryFrom<Vec<u8>> for RsaPublicKey
     }
 }
 
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+
+    #[test]
+    fn modulus_size()
+    {
+	for &bits in [2048u32, 4096].iter() {
+	    let rsa = Rsa::generate(bits).unwrap();
+	    let size = rsa.size() as usize;
+	    let key = RsaPublicKey::from(rsa);
+
+	    assert_eq!(key.modulus_bits(), bits);
+	    assert_eq!(key.size_bytes(), size);
+	    assert_eq!(key.size_bytes(), bits as usize / 8);
+	}
+	assert_eq!(RsaPublicKey::generate().unwrap().modulus_bits(), consts::RSA_KEY_BITS);
+    }
+
+    #[test]
+    fn from_bytes_overflow()
+    {
+	// The lengths sum to exactly `usize::MAX + 1`, which would wrap to an empty body
+	let half = usize::MAX / 2 + 1;
+	let bytes = Vec::from(bytes::refer(&PublicOffsetGroup { n: half, e: half }));
+
+	assert!(matches!(RsaPublicKey::from_bytes(&bytes), Err(Error::Binary(BinaryErrorKind::Corruption))));
+	assert_eq!(RsaPublicKey::read_sync(&mut &bytes[..]).unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn serialized_len()
+    {
+	let key = RsaPrivateKey::generate().unwrap().get_public_parts();
+	assert_eq!(key.serialized_len(), key.to_bytes().len());
+	assert_eq!(key.serialized_len(), key.write_to_sync(&mut Vec::new()).unwrap());
+	assert_eq!(key.serialized_len() - key.len(), size_of::<PublicOffsetGroup>());
+	assert!(!key.is_empty());
+    }
+
+    #[test]
+    fn from_bytes_unaligned()
+    {
+	let key = RsaPrivateKey::generate().unwrap().get_public_parts();
+	let mut bytes = vec![0u8];
+	bytes.extend_from_slice(&key.to_bytes()[..]);
+
+	assert_eq!(RsaPublicKey::from_bytes(&bytes[1..]).unwrap(), key);
+	assert!(matches!(RsaPublicKey::from_bytes(&bytes[1..4]), Err(Error::Binary(BinaryErrorKind::Length{..}))));
+    }
+
+    #[test]
+    fn same_key()
+    {
+	let key = RsaPrivateKey::generate().unwrap().get_public_parts();
+
+	let mut data = vec![0u8];
+	data.extend_from_slice(key.n());
+	data.extend_from_slice(key.e());
+	let offset = PublicOffsetGroup {
+	    n: key.n().len() + 1,
+	    e: key.e().len(),
+	};
+	let padded = RsaPublicKey {
+	    data,
+	    offset_starts: offset.starts(),
+	    offset,
+	};
+
+	// The binary representations differ, so `==` does not consider these equal
+	assert_ne!(padded, key);
+	assert_ne!(padded.cmp(&key), Ordering::Equal);
+	assert!(padded.same_key(&key));
+	assert!(key.same_key(&padded));
+
+	let other = RsaPrivateKey::generate().unwrap().get_public_parts();
+	assert!(!other.same_key(&key));
+	assert_ne!(other.cmp(&key), Ordering::Equal);
+	assert_eq!(other.cmp(&key), other.cmp(&padded));
+
+	let same = RsaPublicKey::new(key.num_n(), key.num_e());
+	assert_eq!(same.cmp(&key), Ordering::Equal);
+
+	let mut set = std::collections::BTreeSet::new();
+	assert!(set.insert(key.clone()));
+	assert!(set.insert(other.clone()));
+	assert!(!set.insert(same.clone()));
+	assert!(set.insert(padded.clone()));
+	assert_eq!(set.len(), 3);
+
+	let set: std::collections::BTreeSet<_> = vec![key.clone(), other.clone(), same, padded.clone()].into_iter().collect();
+	assert_eq!(set.len(), 3);
+	assert!(set.contains(&padded));
+	assert!(set.contains(&other));
+    }
+
+    #[test]
+    fn from_str()
+    {
+	let key = RsaPrivateKey::generate().unwrap().get_public_parts();
+	let pem = key.to_pem().unwrap();
+
+	assert_eq!(pem.parse::<RsaPublicKey>().unwrap(), key);
+	assert_eq!(RsaPublicKey::try_from(&pem[..]).unwrap(), key);
+	assert!("not a key".parse::<RsaPublicKey>().is_err());
+    }
+
+    #[test]
+    fn from_pem_many()
+    {
+	let first = RsaPrivateKey::generate().unwrap().get_public_parts();
+	let second = RsaPrivateKey::generate().unwrap().get_public_parts();
+	let bundle = format!("{}\n{}", first.to_pem().unwrap(), second.to_pem().unwrap());
+
+	assert_eq!(RsaPublicKey::from_pem_many(&bundle).unwrap(), vec![first.clone(), second.clone()]);
+	assert!(RsaPublicKey::from_pem_many("").unwrap().is_empty());
+
+	let second_pem = second.to_pem().unwrap();
+	let (header, body) = second_pem.split_at(second_pem.find('\n').unwrap() + 1);
+	let bad = format!("{}{}not base64!{}", first.to_pem().unwrap(), header, body);
+	assert!(matches!(RsaPublicKey::from_pem_many(&bad), Err(Error::PEMBlock(1))));
+
+	let unterminated = format!("{}-----BEGIN PUBLIC KEY-----\nAAAA\n", first.to_pem().unwrap());
+	assert!(matches!(RsaPublicKey::from_pem_many(&unterminated), Err(Error::PEMBlock(1))));
+    }
+
+    #[test]
+    fn ordering()
+    {
+	assert_eq!(cmp_be(&[0, 0, 5], &[5]), Ordering::Equal);
+	assert_eq!(cmp_be(&[1, 0], &[0, 0xff]), Ordering::Greater);
+	assert_eq!(cmp_be(&[0xff], &[1, 0]), Ordering::Less);
+	assert_eq!(cmp_be(&[], &[0]), Ordering::Equal);
+
+	let small = RsaPublicKey::new(openssl::bn::BigNum::from_u32(100).unwrap(), openssl::bn::BigNum::from_u32(3).unwrap());
+	let big = RsaPublicKey::new(openssl::bn::BigNum::from_u32(0x10000).unwrap(), openssl::bn::BigNum::from_u32(3).unwrap());
+	let big_e = RsaPublicKey::new(openssl::bn::BigNum::from_u32(0x10000).unwrap(), openssl::bn::BigNum::from_u32(65537).unwrap());
+	assert!(small < big);
+	assert!(big < big_e);
+    }
+}
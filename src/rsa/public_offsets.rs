@@ -10,6 +10,9 @@ pub struct PublicOffsetGroup
     pub e: usize,
 }
 
+impl crate::bytes::sealed::Sealed for PublicOffsetGroup{}
+impl crate::bytes::Pod for PublicOffsetGroup{}
+
 impl HasOffsets for PublicOffsetGroup
 {
     fn starts(&self) -> Starts<Self>
@@ -19,9 +22,9 @@ impl HasOffsets for PublicOffsetGroup
 	    e: self.n,
 	}.into()
     }
-    fn body_len(&self) -> usize
+    fn body_len(&self) -> Option<usize>
     {
-	self.n+self.e
+	self.n.checked_add(self.e)
     }
 }
 
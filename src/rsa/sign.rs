@@ -11,8 +11,10 @@ use std::{
     },
     marker::Unpin,
     io::{
+	self,
 	Read,
     },
+    convert::TryFrom,
 };
 use openssl::{
     hash::{
@@ -22,14 +24,23 @@ use openssl::{
 	Signer,
 	Verifier,
     },
+    md::Md,
+    pkey_ctx::PkeyCtx,
+    rsa::Padding,
     pkey::{
 	HasPrivate,
+	PKey,
+	Private,
+	Public,
     },
 };
+use zeroize::Zeroizing;
 #[cfg(feature="async")] 
 use tokio::io::{
     AsyncRead,
     AsyncReadExt,
+    AsyncWrite,
+    AsyncWriteExt,
 };
 use consts::RSA_SIG_SIZE as SIZE;
 use consts::BUFFER_SIZE;
@@ -115,6 +126,7 @@ impl Default for Signature
 
 #[cfg(feature="serialise")]
 #[cfg(test)]
+#[allow(clippy::items_after_test_module)]
 mod serde_tests
 {
     
@@ -165,6 +177,65 @@ impl Signature
 	assert_eq!(bytes::copy_slice(&mut output[..], from.as_ref()), SIZE);
 	Self(output)
     }
+
+    /// Compare with another signature in constant time.
+    ///
+    /// # Notes
+    /// The `PartialEq` and `Ord` implementations exit early and should only be used for sorting and maps, use this when comparing against an expected signature.
+    #[inline] pub fn ct_eq(&self, other: &Signature) -> bool
+    {
+	bytes::ct_eq(&self.0[..], &other.0[..])
+    }
+
+    /// A reader over the bytes of this signature, for composing with other `Read`s
+    #[inline] pub fn reader(&self) -> impl Read + '_
+    {
+	io::Cursor::new(&self.0[..])
+    }
+
+    /// Encode this signature as a lowercase hex string
+    pub fn to_hex(&self) -> String
+    {
+	bytes::to_hex(&self.0[..])
+    }
+
+    /// Decode a signature from a hex string.
+    ///
+    /// Fails if the string is not valid hex, or does not decode to exactly `RSA_SIG_SIZE` bytes.
+    pub fn from_hex(from: impl AsRef<str>) -> Result<Self, Error>
+    {
+	let bytes = bytes::from_hex(from.as_ref()).ok_or(Error::Binary(BinaryErrorKind::Corruption))?;
+	Self::try_from(&bytes[..])
+    }
+
+    /// Encode this signature as a base64 string
+    #[inline] pub fn to_base64(&self) -> String
+    {
+	base64::encode(&self.0[..])
+    }
+
+    /// Decode a signature from a base64 string.
+    ///
+    /// Fails if the string is not valid base64, or does not decode to exactly `RSA_SIG_SIZE` bytes.
+    pub fn from_base64(from: impl AsRef<str>) -> Result<Self, Error>
+    {
+	let bytes = base64::decode(from.as_ref()).map_err(|_| Error::Binary(BinaryErrorKind::Corruption))?;
+	Self::try_from(&bytes[..])
+    }
+
+    /// Try to create from a slice.
+    ///
+    /// Fails if `from` is not at least `RSA_SIG_SIZE` bytes long. Any bytes past that are ignored.
+    pub fn try_from_slice(from: impl AsRef<[u8]>) -> Result<Self, Error>
+    {
+	let from = from.as_ref();
+	if from.len() < SIZE {
+	    return Err(Error::Binary(BinaryErrorKind::Length{expected: Some(SIZE), got: Some(from.len())}));
+	}
+	let mut output = [0u8; SIZE];
+	bytes::copy_slice(&mut output[..], from);
+	Ok(Self(output))
+    }
     
     /// Verify this signature for a slice of data
     pub fn verify_slice<T,K>(&self, slice: T, key: &K) -> Result<bool, Error>
@@ -179,6 +250,48 @@ impl Signature
 	Ok(veri.verify(&self.0[..])?)
     }
 
+    /// Verify this signature for a slice of data against each of `keys` in turn. Returns the index of the first key it is valid for, or `None` if there are none.
+    ///
+    /// # Errors
+    /// A key that fails to verify with an error is skipped. The last error is only returned if every key failed with one.
+    pub fn verify_slice_any<T,K>(&self, slice: T, keys: &[K]) -> Result<Option<usize>, Error>
+    where K: PublicKey,
+	  T: AsRef<[u8]>
+    {
+	let slice = slice.as_ref();
+	let mut last_error = None;
+	let mut all_failed = true;
+	for (i, key) in keys.iter().enumerate() {
+	    match self.verify_slice(slice, key) {
+		Ok(true) => return Ok(Some(i)),
+		Ok(false) => all_failed = false,
+		Err(err) => last_error = Some(err),
+	    }
+	}
+	match last_error {
+	    Some(err) if all_failed => Err(err),
+	    _ => Ok(None),
+	}
+    }
+
+    /// Verify this signature against an already computed SHA256 hash of the data.
+    ///
+    /// # Notes
+    /// This verifies the same signatures as `verify_slice()` does for the data itself, without reading the data again.
+    pub fn verify_prehashed<K>(&self, digest: &sha256::Sha256Hash, key: &K) -> Result<bool, Error>
+    where K: PublicKey + ?Sized
+    {
+	let pkey = key.get_pkey_pub().map_err(|_| Error::Key)?;
+
+	let mut ctx = PkeyCtx::new(&pkey)?;
+	ctx.verify_init()?;
+	ctx.set_rsa_padding(Padding::PKCS1)?;
+	ctx.set_signature_md(Md::sha256())?;
+
+	// Like `Verifier::verify()`, a signature that does not match is `false` rather than an error
+	Ok(ctx.verify(digest.as_ref(), &self.0[..]).unwrap_or(false))
+    }
+
     /// Verify this signature for a stream of data. Returns the success and number of bytes read.
     #[cfg(feature="async")] 
     pub async fn verify<T,K>(&self, from: &mut T, key: &K) -> Result<(bool, usize), Error>
@@ -201,6 +314,33 @@ impl Signature
 
 	Ok((veri.verify(&self.0[..])?, done))
     }
+    /// Verify this signature for a stream of data, writing the data read into `sink` as it is verified. Returns the success and number of bytes read.
+    ///
+    /// # Notes
+    /// The data is written to `sink` regardless of whether the signature turns out to be valid.
+    #[cfg(feature="async")] 
+    pub async fn verify_into<T,K,W>(&self, from: &mut T, key: &K, sink: &mut W) -> Result<(bool, usize), Error>
+    where T: AsyncRead + Unpin + ?Sized,
+	  K: PublicKey + ?Sized,
+	  W: AsyncWrite + Unpin + ?Sized
+    {
+	let pkey = key.get_pkey_pub().map_err(|_| Error::Key)?;
+
+	let mut veri = Verifier::new(MessageDigest::sha256(), &pkey)?;
+	let done = {
+	    let mut read;
+	    let mut done = 0;
+	    let mut buffer = [0u8; BUFFER_SIZE];
+	    while {read = from.read(&mut buffer[..]).await?; read!=0} {
+		veri.update(&buffer[..read])?;
+		sink.write_all(&buffer[..read]).await?;
+		done+=read;
+	    }
+	    done
+	};
+
+	Ok((veri.verify(&self.0[..])?, done))
+    }
     /// Verify this signature for a stream of data. Returns the success and number of bytes read.
     pub fn verify_sync<T,K>(&self, from: &mut T, key: &K) -> Result<(bool, usize), Error>
     where T: Read + ?Sized,
@@ -241,6 +381,26 @@ where T: AsRef<[u8]>,
     Ok(Signature(output))
 }
 
+/// Compute the signature for a number of slices, as if they were one contiguous slice
+pub fn sign_slices<I,T,K>(chunks: I, key: &K) -> Result<Signature, Error>
+where I: IntoIterator<Item=T>,
+      T: AsRef<[u8]>,
+      K: PrivateKey + ?Sized,
+<K as PublicKey>::KeyType: HasPrivate
+{
+    let pkey = key.get_pkey_priv().map_err(|_| Error::Key)?;
+
+    let mut signer = Signer::new(MessageDigest::sha256(), &pkey)?;
+    for chunk in chunks.into_iter() {
+	signer.update(chunk.as_ref())?;
+    }
+
+    let mut output = [0u8; SIZE];
+    assert_eq!(signer.sign(&mut output[..])?, SIZE);
+
+    Ok(Signature(output))
+}
+
 /// Compute the signature for this stream, returning it and the number of bytes read
 #[cfg(feature="async")] 
 pub async fn sign<T,K>(data: &mut T, key: &K) -> Result<(Signature, usize), Error>
@@ -255,7 +415,7 @@ where T: AsyncRead + Unpin + ?Sized,
     let done = {
 	let mut read;
 	let mut done=0;
-	let mut buffer = [0u8; SIZE];
+	let mut buffer = [0u8; BUFFER_SIZE];
 
 	while {read = data.read(&mut buffer[..]).await?; read!=0} {
 	    signer.update(&buffer[..read])?;
@@ -282,7 +442,7 @@ where T: Read + ?Sized,
     let done = {
 	let mut read;
 	let mut done=0;
-	let mut buffer = [0u8; SIZE];
+	let mut buffer = [0u8; BUFFER_SIZE];
 
 	while {read = data.read(&mut buffer[..])?; read!=0} {
 	    signer.update(&buffer[..read])?;
@@ -297,6 +457,142 @@ where T: Read + ?Sized,
     Ok((Signature(output), done))
 }
 
+/// Verify a stream that has its signature appended to the end, as the last `RSA_SIG_SIZE` bytes.
+///
+/// All data before the signature is verified against it, without needing to know the length of the stream in advance.
+///
+/// # Errors
+/// `Error::Binary` if the stream is shorter than a signature.
+#[cfg(feature="async")] 
+pub async fn verify_trailing<T,K>(from: &mut T, key: &K) -> Result<bool, Error>
+where T: AsyncRead + Unpin + ?Sized,
+      K: PublicKey + ?Sized
+{
+    let pkey = key.get_pkey_pub().map_err(|_| Error::Key)?;
+
+    let mut veri = Verifier::new(MessageDigest::sha256(), &pkey)?;
+    let mut buffer = [0u8; BUFFER_SIZE + SIZE];
+    let mut held = 0;
+    let mut read;
+    while {read = from.read(&mut buffer[held..]).await?; read!=0} {
+	held += read;
+	// Everything except the last `SIZE` bytes so far cannot be part of the signature
+	if held > SIZE {
+	    veri.update(&buffer[..held-SIZE])?;
+	    buffer.copy_within(held-SIZE..held, 0);
+	    held = SIZE;
+	}
+    }
+    if held < SIZE {
+	return Err(Error::Binary(BinaryErrorKind::Length{expected: Some(SIZE), got: Some(held)}));
+    }
+
+    Ok(veri.verify(&buffer[..SIZE])?)
+}
+
+/// Verify a stream that has its signature appended to the end, as the last `RSA_SIG_SIZE` bytes.
+///
+/// All data before the signature is verified against it, without needing to know the length of the stream in advance.
+///
+/// # Errors
+/// `Error::Binary` if the stream is shorter than a signature.
+pub fn verify_trailing_sync<T,K>(from: &mut T, key: &K) -> Result<bool, Error>
+where T: Read + ?Sized,
+      K: PublicKey + ?Sized
+{
+    let pkey = key.get_pkey_pub().map_err(|_| Error::Key)?;
+
+    let mut veri = Verifier::new(MessageDigest::sha256(), &pkey)?;
+    let mut buffer = [0u8; BUFFER_SIZE + SIZE];
+    let mut held = 0;
+    let mut read;
+    while {read = from.read(&mut buffer[held..])?; read!=0} {
+	held += read;
+	// Everything except the last `SIZE` bytes so far cannot be part of the signature
+	if held > SIZE {
+	    veri.update(&buffer[..held-SIZE])?;
+	    buffer.copy_within(held-SIZE..held, 0);
+	    held = SIZE;
+	}
+    }
+    if held < SIZE {
+	return Err(Error::Binary(BinaryErrorKind::Length{expected: Some(SIZE), got: Some(held)}));
+    }
+
+    Ok(veri.verify(&buffer[..SIZE])?)
+}
+
+/// A signing key imported once, for signing many messages without rebuilding the key each time
+pub struct RsaSigner
+{
+    pkey: PKey<Private>,
+}
+
+impl RsaSigner
+{
+    /// Import `key` for signing
+    pub fn new<K>(key: &K) -> Result<Self, Error>
+    where K: PrivateKey + ?Sized,
+    <K as PublicKey>::KeyType: HasPrivate
+    {
+	let pkey = key.get_pkey_priv().map_err(|_| Error::Key)?;
+	// The key type is generic, so it is converted through DER. Don't leave the private key behind in it.
+	let der = Zeroizing::new(pkey.private_key_to_der()?);
+	Ok(Self {
+	    pkey: PKey::private_key_from_der(&der[..])?,
+	})
+    }
+
+    /// Compute the signature for a slice of bytes. This is the same as `sign_slice()` with the key this signer was created from.
+    pub fn sign<T>(&self, data: T) -> Result<Signature, Error>
+    where T: AsRef<[u8]>
+    {
+	sign_slice(data, &self.pkey)
+    }
+}
+
+impl fmt::Debug for RsaSigner
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+	f.debug_struct("RsaSigner").finish_non_exhaustive()
+    }
+}
+
+/// A verifying key imported once, for verifying many signatures without rebuilding the key each time
+pub struct RsaVerifier
+{
+    pkey: PKey<Public>,
+}
+
+impl RsaVerifier
+{
+    /// Import the public part of `key` for verifying
+    pub fn new<K>(key: &K) -> Result<Self, Error>
+    where K: PublicKey + ?Sized
+    {
+	let pkey = key.get_pkey_pub().map_err(|_| Error::Key)?;
+	Ok(Self {
+	    pkey: PKey::public_key_from_der(&pkey.public_key_to_der()?[..])?,
+	})
+    }
+
+    /// Verify `signature` for a slice of data. This is the same as `Signature::verify_slice()` with the key this verifier was created from.
+    #[inline] pub fn verify<T>(&self, data: T, signature: &Signature) -> Result<bool, Error>
+    where T: AsRef<[u8]>
+    {
+	signature.verify_slice(data, &self.pkey)
+    }
+}
+
+impl fmt::Debug for RsaVerifier
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+	f.debug_struct("RsaVerifier").finish_non_exhaustive()
+    }
+}
+
 // Boilerplate
 
 impl AsRef<[u8]> for Signature
@@ -315,16 +611,269 @@ impl AsMut<[u8]> for Signature
     }
 }
 
+impl TryFrom<&[u8]> for Signature
+{
+    type Error = Error;
+
+    /// Fails if `from` is not exactly `RSA_SIG_SIZE` bytes long
+    fn try_from(from: &[u8]) -> Result<Self, Self::Error>
+    {
+	if from.len() != SIZE {
+	    return Err(Error::Binary(BinaryErrorKind::Length{expected: Some(SIZE), got: Some(from.len())}));
+	}
+	let mut output = [0u8; SIZE];
+	bytes::copy_slice(&mut output[..], from);
+	Ok(Self(output))
+    }
+}
+
 impl Display for Signature
 {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
     {
-	write!(f, "Signature (")?;
-	for byte in self.0.iter()
+	write!(f, "Signature ({:x})", self)
+    }
+}
+
+impl fmt::LowerHex for Signature
+{
+    #[inline] fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+	bytes::fmt_hex(&self.0[..], f, false)
+    }
+}
+
+impl fmt::UpperHex for Signature
+{
+    #[inline] fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+	bytes::fmt_hex(&self.0[..], f, true)
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use std::convert::TryFrom;
+    use super::{Signature, SIZE};
+
+    #[test]
+    fn reader()
+    {
+	use std::io::Read;
+	let signature = Signature::from_exact([0xa5u8; SIZE]);
+
+	let mut output = Vec::new();
+	assert_eq!(signature.reader().read_to_end(&mut output).unwrap(), SIZE);
+	assert_eq!(&output[..], signature.as_ref());
+
+	let mut output = Vec::new();
+	std::io::copy(&mut signature.reader().chain(&b"trailer"[..]), &mut output).unwrap();
+	assert_eq!(&output[..SIZE], signature.as_ref());
+	assert_eq!(&output[SIZE..], b"trailer");
+    }
+
+    #[test]
+    fn try_from_slice()
+    {
+	let data = [0xa5u8; SIZE + 1];
+	assert_eq!(Signature::try_from(&data[..SIZE]).unwrap(), Signature::from_exact([0xa5u8; SIZE]));
+	assert!(Signature::try_from(&data[..SIZE-1]).is_err());
+	assert!(Signature::try_from(&data[..]).is_err());
+    }
+
+    #[test]
+    fn try_from_slice_short()
+    {
+	let data = [0xa5u8; SIZE + 1];
+	assert_eq!(Signature::try_from_slice(&data[..SIZE]).unwrap(), Signature::from_exact([0xa5u8; SIZE]));
+	assert_eq!(Signature::try_from_slice(&data[..]).unwrap(), Signature::from_exact([0xa5u8; SIZE]));
+	assert!(matches!(Signature::try_from_slice(&data[..SIZE-1]), Err(super::Error::Binary(super::BinaryErrorKind::Length{expected: Some(SIZE), got: Some(got)})) if got == SIZE-1));
+	assert!(Signature::try_from_slice([]).is_err());
+    }
+
+    #[test]
+    fn hex_round_trip()
+    {
+	let mut bytes = [0u8; SIZE];
+	for (i, byte) in bytes.iter_mut().enumerate() {
+	    *byte = i as u8;
+	}
+	let signature = Signature::from_exact(bytes);
+	let hex = signature.to_hex();
+
+	assert_eq!(hex.len(), SIZE * 2);
+	assert!(hex.starts_with("000102"));
+	assert_eq!(format!("{}", signature), format!("Signature ({})", hex));
+	assert_eq!(format!("{:x}", signature), hex);
+	assert_eq!(format!("{:X}", signature), hex.to_uppercase());
+	assert_eq!(Signature::from_hex(&hex).unwrap(), signature);
+	assert_eq!(Signature::from_hex(hex.to_uppercase()).unwrap(), signature);
+
+	assert!(matches!(Signature::from_hex(&hex[2..]), Err(super::Error::Binary(super::BinaryErrorKind::Length{..}))));
+	assert!(matches!(Signature::from_hex(hex.replace("01", "0g")), Err(super::Error::Binary(super::BinaryErrorKind::Corruption))));
+	assert!(Signature::from_hex(format!("é{}", &hex[2..])).is_err());
+    }
+
+    #[test]
+    fn base64_round_trip()
+    {
+	let signature = Signature::from_exact([0xa5u8; SIZE]);
+	let string = signature.to_base64();
+
+	assert_eq!(Signature::from_base64(&string).unwrap(), signature);
+	assert!(matches!(Signature::from_base64(base64::encode(&[0xa5u8; SIZE-1][..])), Err(super::Error::Binary(super::BinaryErrorKind::Length{..}))));
+	assert!(matches!(Signature::from_base64("not base64!"), Err(super::Error::Binary(super::BinaryErrorKind::Corruption))));
+    }
+
+    #[test]
+    fn verify_prehashed()
+    {
+	let pv = super::RsaPrivateKey::generate().expect("genkey");
+	let data = b"some data that was hashed earlier";
+	let signature = super::sign_slice(&data[..], &pv).expect("sign");
+
+	assert!(signature.verify_prehashed(&crate::sha256::compute_slice(&data[..]), &pv).expect("verify"));
+	assert!(!signature.verify_prehashed(&crate::sha256::compute_slice(&data[1..]), &pv).expect("verify"));
+	assert!(!Signature::default().verify_prehashed(&crate::sha256::compute_slice(&data[..]), &pv).expect("verify"));
+    }
+
+    #[test]
+    fn verify_slice_any()
+    {
+	let keys: Vec<_> = (0..4).map(|_| super::RsaPrivateKey::generate().expect("genkey")).collect();
+	let data = b"some data signed by one of the keys";
+	let signature = super::sign_slice(&data[..], &keys[2]).expect("sign");
+
+	assert_eq!(signature.verify_slice_any(&data[..], &keys[..]).expect("verify"), Some(2));
+	assert_eq!(signature.verify_slice_any(&data[..], &keys[..2]).expect("verify"), None);
+	assert_eq!(signature.verify_slice_any(&data[1..], &keys[..]).expect("verify"), None);
+	assert_eq!(signature.verify_slice_any::<_, super::RsaPrivateKey>(&data[..], &[]).expect("verify"), None);
+    }
+
+    #[test]
+    fn sign_large_stream()
+    {
+	let pv = super::RsaPrivateKey::generate().expect("genkey");
+	let data: Vec<u8> = (0..4 * 1024 * 1024 + 13).map(|x| (x % 251) as u8).collect();
+
+	let (signature, read) = super::sign_sync(&mut &data[..], &pv).expect("sign");
+	assert_eq!(read, data.len());
+	assert_eq!(signature, super::sign_slice(&data[..], &pv).expect("sign"));
+	assert!(signature.verify_sync(&mut &data[..], &pv).expect("verify").0);
+    }
+
+    #[test]
+    fn sign_slices()
+    {
+	let pv = super::RsaPrivateKey::generate().expect("genkey");
+	let data: Vec<u8> = (0..super::BUFFER_SIZE * 3 + 13).map(|x| x as u8).collect();
+
+	let signature = super::sign_slice(&data[..], &pv).expect("sign");
+	assert_eq!(super::sign_slices(data.chunks(7), &pv).expect("sign"), signature);
+	assert_eq!(super::sign_slices(vec![&data[..1], &[], &data[1..]], &pv).expect("sign"), signature);
+	assert_eq!(super::sign_slices(std::iter::empty::<&[u8]>(), &pv).expect("sign"), super::sign_slice([], &pv).expect("sign"));
+    }
+
+    #[test]
+    fn reusable_signer()
+    {
+	let pv = super::RsaPrivateKey::generate().expect("genkey");
+	let signer = super::RsaSigner::new(&pv).expect("signer");
+	let verifier = super::RsaVerifier::new(&pv.get_public_parts()).expect("verifier");
+
+	for len in 0..16 {
+	    let data: Vec<u8> = (0..len * 37).map(|x| x as u8).collect();
+	    let signature = signer.sign(&data[..]).expect("sign");
+	    assert_eq!(signature, super::sign_slice(&data[..], &pv).expect("sign"));
+	    assert!(verifier.verify(&data[..], &signature).expect("verify"));
+	    assert!(!verifier.verify(&data[..], &Signature::default()).unwrap_or(false));
+	}
+	assert_eq!(format!("{:?}", signer), "RsaSigner { .. }");
+    }
+
+    #[test]
+    fn verify_trailing()
+    {
+	/// Reads at most `n` bytes at a time, so the signature is split across reads
+	struct Trickle<'a>(&'a [u8], usize);
+	impl std::io::Read for Trickle<'_>
 	{
-	    write!(f, "{:0x}", byte)?;
+	    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize>
+	    {
+		let len = std::cmp::min(buf.len(), self.1);
+		std::io::Read::read(&mut self.0, &mut buf[..len])
+	    }
 	}
-	write!(f,")")
+
+	let pv = super::RsaPrivateKey::generate().expect("genkey");
+	for &len in [0, 1, SIZE, super::BUFFER_SIZE * 3 + 13].iter() {
+	    let data: Vec<u8> = (0..len).map(|x| x as u8).collect();
+	    let mut blob = data.clone();
+	    blob.extend_from_slice(super::sign_slice(&data[..], &pv).expect("sign").as_ref());
+
+	    assert!(super::verify_trailing_sync(&mut &blob[..], &pv).expect("verify"));
+	    assert!(super::verify_trailing_sync(&mut Trickle(&blob[..], 7), &pv).expect("verify"));
+	    assert!(super::verify_trailing_sync(&mut Trickle(&blob[..], SIZE + 1), &pv).expect("verify"));
+
+	    let mut bad = blob.clone();
+	    bad[0] ^= 1;
+	    assert!(!super::verify_trailing_sync(&mut &bad[..], &pv).unwrap_or(false));
+	}
+	assert!(matches!(super::verify_trailing_sync(&mut &[0u8; SIZE - 1][..], &pv), Err(super::Error::Binary(_))));
+    }
+
+    #[cfg(feature="async")]
+    #[tokio::test]
+    async fn verify_trailing_async()
+    {
+	let pv = super::RsaPrivateKey::generate().expect("genkey");
+	let data: Vec<u8> = (0..super::BUFFER_SIZE * 3 + 13).map(|x| x as u8).collect();
+	let mut blob = data.clone();
+	blob.extend_from_slice(super::sign_slice(&data[..], &pv).expect("sign").as_ref());
+
+	assert!(super::verify_trailing(&mut &blob[..], &pv).await.expect("verify"));
+	assert!(!super::verify_trailing(&mut &blob[1..], &pv).await.unwrap_or(false));
     }
-}
 
+    #[test]
+    fn ct_eq()
+    {
+	let a = Signature::from_exact([0xa5u8; SIZE]);
+	let mut b = a;
+	assert!(a.ct_eq(&b));
+	assert_eq!(a.ct_eq(&b), a == b);
+
+	b.0[SIZE-1] ^= 1;
+	assert!(!a.ct_eq(&b));
+	assert_eq!(a.ct_eq(&b), a == b);
+    }
+
+    #[test]
+    #[should_panic]
+    fn from_slice_short()
+    {
+	let _ = Signature::from_slice([0u8; SIZE-1]);
+    }
+
+    #[cfg(feature="async")]
+    #[tokio::test]
+    async fn verify_into()
+    {
+	let pv = super::RsaPrivateKey::generate().expect("genkey");
+	let data = vec![0x5au8; super::BUFFER_SIZE * 2 + 9];
+	let signature = super::sign_slice(&data[..], &pv).expect("sign");
+
+	let mut output = Vec::new();
+	let (ok, read) = signature.verify_into(&mut &data[..], &pv, &mut output).await.expect("verify");
+	assert!(ok);
+	assert_eq!(read, data.len());
+	assert_eq!(output, data);
+
+	let mut output = Vec::new();
+	let other = super::sign_slice(&data[1..], &pv).expect("sign");
+	let (ok, _) = other.verify_into(&mut &data[..], &pv, &mut output).await.expect("verify");
+	assert!(!ok);
+	assert_eq!(output, data);
+    }
+}
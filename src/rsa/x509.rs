@@ -0,0 +1,85 @@
+//! Public keys from X.509 certificates
+use super::*;
+use openssl::{
+    x509::X509,
+};
+
+impl RsaPublicKey
+{
+    /// Try to create a new instance from the subject public key of a PEM encoded X.509 certificate.
+    ///
+    /// # Notes
+    /// Only the key is extracted. The certificate's signature, validity period and issuer are not checked.
+    ///
+    /// # Errors
+    /// `Error::Key` if the certificate's key is not an RSA key.
+    pub fn from_x509_pem(pem: impl AsRef<str>) -> Result<Self, Error>
+    {
+	let cert = X509::from_pem(pem.as_ref().as_bytes())?;
+	let rsa = cert.public_key()?.rsa().map_err(|_| Error::Key)?;
+	Ok(rsa.into())
+    }
+}
+
+#[cfg(test)]
+mod tests
+{
+    use super::*;
+    use openssl::{
+	asn1::Asn1Time,
+	ec::{
+	    EcGroup,
+	    EcKey,
+	},
+	hash::MessageDigest,
+	nid::Nid,
+	pkey::{
+	    PKey,
+	    Private,
+	},
+	x509::X509NameBuilder,
+    };
+
+    /// A self-signed certificate for `key`
+    fn self_signed(key: &PKey<Private>) -> String
+    {
+	let mut name = X509NameBuilder::new().unwrap();
+	name.append_entry_by_text("CN", "cryptohelpers test").unwrap();
+	let name = name.build();
+
+	let mut cert = X509::builder().unwrap();
+	cert.set_version(2).unwrap();
+	cert.set_subject_name(&name).unwrap();
+	cert.set_issuer_name(&name).unwrap();
+	cert.set_pubkey(key).unwrap();
+	cert.set_not_before(&Asn1Time::days_from_now(0).unwrap()).unwrap();
+	cert.set_not_after(&Asn1Time::days_from_now(1).unwrap()).unwrap();
+	cert.sign(key, MessageDigest::sha256()).unwrap();
+
+	String::from_utf8(cert.build().to_pem().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn from_x509_pem()
+    {
+	let data = b"some data to sign";
+	let pv = RsaPrivateKey::generate().unwrap();
+	let pem = self_signed(&pv.get_pkey_priv().unwrap());
+
+	let pb = RsaPublicKey::from_x509_pem(&pem).unwrap();
+	assert_eq!(pb, pv.get_public_parts());
+
+	let signature = sign_slice(data, &pv).unwrap();
+	assert!(signature.verify_slice(data, &pb).unwrap());
+    }
+
+    #[test]
+    fn from_x509_pem_invalid()
+    {
+	let ec = EcKey::generate(&EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap()).unwrap();
+	let pem = self_signed(&PKey::from_ec_key(ec).unwrap());
+
+	assert!(matches!(RsaPublicKey::from_x509_pem(&pem), Err(Error::Key)));
+	assert!(RsaPublicKey::from_x509_pem("not a certificate").is_err());
+    }
+}
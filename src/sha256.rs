@@ -1,10 +1,21 @@
 //! Deals with SHA256 hashing
 use super::*;
 #[allow(unused_imports)]
-use std::{
+use core::{
     fmt,
     marker::Unpin,
-    io,
+    convert::TryFrom,
+    array::TryFromSliceError,
+};
+#[cfg(feature="std")]
+use std::io;
+#[cfg(feature="fs")]
+use std::{
+    fs,
+    path::{
+	Path,
+	PathBuf,
+    },
 };
 use sha2::{
     Digest, Sha256,
@@ -13,10 +24,25 @@ use sha2::{
 use tokio::io::{
     AsyncRead,
     AsyncReadExt,
+    AsyncBufRead,
+    AsyncBufReadExt,
+    AsyncWrite,
+};
+#[cfg(feature="async")]
+use core::{
+    pin::Pin,
+    task::{Context, Poll},
 };
 
 pub const SIZE: usize = consts::SHA256_SIZE;
 
+/// Checks the size passed to `Sha256Hash::truncate_to()`, the check needs the generic `N` so it cannot be a module level assertion
+struct TruncateTo<const N: usize>;
+impl<const N: usize> TruncateTo<N>
+{
+    const CHECK: () = assert!(N <= SIZE, "cannot truncate a SHA256 hash to more than SIZE bytes");
+}
+
 /// Represents a SHA256 hash
 #[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
 #[repr(transparent)]
@@ -46,7 +72,49 @@ impl Sha256Hash
         self.hash
     }
 
+    /// Borrow the bytes of this hash as a fixed-size array
+    #[inline] pub const fn as_array(&self) -> &[u8; SIZE]
+    {
+	&self.hash
+    }
+
+    /// Mutably borrow the bytes of this hash as a fixed-size array
+    #[inline] pub fn as_array_mut(&mut self) -> &mut [u8; SIZE]
+    {
+	&mut self.hash
+    }
+
+    /// Combine this hash with another by XORing their bytes
+    pub fn xor(&self, other: &Sha256Hash) -> Sha256Hash
+    {
+	let mut hash = self.hash;
+	for (hash, other) in hash.iter_mut().zip(other.hash.iter()) {
+	    *hash ^= other;
+	}
+	Self { hash }
+    }
+
+    /// The first `N` bytes of this hash, e.g. to use a hash as a 16 byte AES key.
+    ///
+    /// `N` larger than `SIZE` fails to compile.
+    pub fn truncate_to<const N: usize>(&self) -> [u8; N]
+    {
+	let () = TruncateTo::<N>::CHECK;
+	let mut output = [0u8; N];
+	bytes::copy_slice(&mut output[..], &self.hash[..]);
+	output
+    }
+
+    /// Zero this hash, making it the same as `empty()`
+    #[inline] pub fn reset(&mut self)
+    {
+	*self = Self::empty();
+    }
+
     /// Reads the rest of the stream, and computes SHA256 hash into the current instance. Returning the number of bytes read.
+    ///
+    /// # Notes
+    /// The current hash is replaced with the hash of the stream, it does not accumulate across calls. Use `Sha256Digest` to hash data from more than one stream.
     #[cfg(feature="async")] 
     pub async fn compute_into<T>(&mut self, from: &mut T) -> io::Result<usize>
     where T: AsyncRead + Unpin + ?Sized
@@ -65,6 +133,10 @@ impl Sha256Hash
     }
     
     /// Reads the rest of the stream, and computes SHA256 hash into the current instance. Returning the number of bytes read.
+    ///
+    /// # Notes
+    /// The current hash is replaced with the hash of the stream, it does not accumulate across calls. Use `Sha256Digest` to hash data from more than one stream.
+    #[cfg(feature="std")]
     pub fn compute_into_sync<T>(&mut self, mut from: T) -> io::Result<usize>
     where T: io::Read
     {
@@ -86,11 +158,23 @@ impl fmt::Display for Sha256Hash
 {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
     {
-	write!(f, "SHA256 (")?;
-	for x in self.hash.iter() {
-	    write!(f, "{:x}", x)?;
-	}
-	write!(f, ")")
+	write!(f, "SHA256 ({:x})", self)
+    }
+}
+
+impl fmt::LowerHex for Sha256Hash
+{
+    #[inline] fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+	bytes::fmt_hex(&self.hash[..], f, false)
+    }
+}
+
+impl fmt::UpperHex for Sha256Hash
+{
+    #[inline] fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result
+    {
+	bytes::fmt_hex(&self.hash[..], f, true)
     }
 }
 
@@ -121,6 +205,159 @@ where T: AsyncRead + Unpin + ?Sized
     Ok(Sha256Hash{hash})
 }
 
+/// Compute the SHA256 hash of the rest of this buffered stream.
+///
+/// The stream's own buffer is hashed directly, instead of being copied through another buffer like `compute()` does.
+#[cfg(feature="async")]
+pub async fn compute_buf<T>(from: &mut T) -> io::Result<Sha256Hash>
+where T: AsyncBufRead + Unpin + ?Sized
+{
+    let mut hasher = Sha256::new();
+    loop {
+	let buffer = from.fill_buf().await?;
+	if buffer.is_empty() {
+	    break;
+	}
+	let read = buffer.len();
+	hasher.update(buffer);
+	from.consume(read);
+    }
+
+    Ok(hasher.into())
+}
+
+/// Compute the SHA256 hash of the next `limit` bytes of this stream.
+///
+/// Nothing after those bytes is read, so the rest of the stream can still be read from `from`.
+///
+/// # Errors
+/// `io::ErrorKind::UnexpectedEof` if the stream ends before `limit` bytes were read.
+#[cfg(feature="async")]
+pub async fn compute_limited<T>(from: &mut T, limit: u64) -> io::Result<Sha256Hash>
+where T: AsyncRead + Unpin + ?Sized
+{
+    let mut buffer = [0u8; super::BUFFER_SIZE];
+    let mut hasher = Sha256::new();
+    let mut remaining = limit;
+    while remaining > 0 {
+	let want = usize::try_from(remaining).map_or(buffer.len(), |remaining| remaining.min(buffer.len()));
+	let read = from.read(&mut buffer[..want]).await?;
+	if read == 0 {
+	    return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "stream ended before the limit"));
+	}
+	hasher.update(&buffer[..read]);
+	remaining -= read as u64;
+    }
+
+    Ok(hasher.into())
+}
+
+/// An incremental SHA256 digest.
+///
+/// Data can be fed in from any number of slices and streams, the result is the same as hashing the concatenation of all of it.
+#[derive(Clone, Default)]
+pub struct Sha256Digest
+{
+    hasher: Sha256,
+}
+
+impl Sha256Digest
+{
+    /// Create a new empty digest
+    #[inline] pub fn new() -> Self
+    {
+	Self::default()
+    }
+
+    /// Feed more data into this digest
+    #[inline] pub fn update(&mut self, data: impl AsRef<[u8]>)
+    {
+	self.hasher.update(data.as_ref());
+    }
+
+    /// Feed the rest of this stream into the digest, returning the number of bytes read.
+    #[cfg(feature="async")]
+    pub async fn update_with_stream<T>(&mut self, from: &mut T) -> io::Result<usize>
+    where T: AsyncRead + Unpin + ?Sized
+    {
+	let mut buffer = [0u8; super::BUFFER_SIZE];
+	let mut read:usize;
+	let mut done=0;
+	while (read = from.read(&mut buffer[..]).await?, read!=0).1 {
+	    self.hasher.update(&buffer[..read]);
+	    done+=read;
+	}
+	Ok(done)
+    }
+
+    /// Feed the rest of this stream into the digest, returning the number of bytes read.
+    #[cfg(feature="std")]
+    pub fn update_with_stream_sync<T>(&mut self, mut from: T) -> io::Result<usize>
+    where T: io::Read
+    {
+	let mut buffer = [0u8; super::BUFFER_SIZE];
+	let mut read:usize;
+	let mut done=0;
+	while (read = from.read(&mut buffer[..])?, read!=0).1 {
+	    self.hasher.update(&buffer[..read]);
+	    done+=read;
+	}
+	Ok(done)
+    }
+
+    /// Consume this digest into the hash of all the data fed into it
+    #[inline] pub fn finalize(self) -> Sha256Hash
+    {
+	self.hasher.into()
+    }
+}
+
+/// An `AsyncWrite` sink that computes the SHA256 hash of everything written to it.
+///
+/// # Notes
+/// Written bytes are discarded. Call `into_hash()` after shutting down to get the hash.
+#[cfg(feature="async")]
+#[derive(Clone, Default)]
+pub struct Sha256Writer
+{
+    hasher: Sha256,
+}
+
+#[cfg(feature="async")]
+impl Sha256Writer
+{
+    /// Create a new hashing sink
+    #[inline] pub fn new() -> Self
+    {
+	Self::default()
+    }
+
+    /// Consume this sink and compute the hash of everything written to it
+    #[inline] pub fn into_hash(self) -> Sha256Hash
+    {
+	self.hasher.into()
+    }
+}
+
+#[cfg(feature="async")]
+impl AsyncWrite for Sha256Writer
+{
+    fn poll_write(self: Pin<&mut Self>, _cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>>
+    {
+	self.get_mut().hasher.update(buf);
+	Poll::Ready(Ok(buf.len()))
+    }
+
+    #[inline] fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>>
+    {
+	Poll::Ready(Ok(()))
+    }
+
+    #[inline] fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>>
+    {
+	Poll::Ready(Ok(()))
+    }
+}
 
 /// Compute SHA256 hash from an iterator of slices.
 pub fn compute_slice_iter<T, I>(from: I) -> Sha256Hash
@@ -150,10 +387,38 @@ where T: AsRef<[u8]>
     Sha256Hash{hash}
 }
 
+/// Size of the chunks hashed independently by `compute_slice_parallel()`
+#[cfg(feature="parallel")]
+pub const PARALLEL_CHUNK_SIZE: usize = 1024 * 1024;
+
+/// Compute a tree hash of a slice using multiple threads.
+///
+/// The input is split into `PARALLEL_CHUNK_SIZE` byte chunks (the last may be shorter) which are hashed in parallel, then the SHA256 hash of the concatenation of those chunk hashes is returned.
+///
+/// # Notes
+/// This is **not** the SHA256 hash of `from`, and will not match `compute_slice()` or any other SHA256 implementation. Only use it to check integrity against other values produced by this function.
+#[cfg(feature="parallel")]
+pub fn compute_slice_parallel<T>(from: T) -> Sha256Hash
+where T: AsRef<[u8]>
+{
+    compute_slice_parallel_chunked(from.as_ref(), PARALLEL_CHUNK_SIZE)
+}
+
+#[cfg(feature="parallel")]
+fn compute_slice_parallel_chunked(from: &[u8], chunk_size: usize) -> Sha256Hash
+{
+    use rayon::prelude::*;
+    let hashes: Vec<Sha256Hash> = from.par_chunks(chunk_size)
+	.map(compute_slice)
+	.collect();
+
+    compute_slices(hashes)
+}
+
 /// Compute a SHA256 hash from a stream of slices
 #[cfg(feature="async")]
 pub async fn compute_slices_stream<T, I>(mut from: I) -> Sha256Hash
-where I: futures::stream::Stream<Item=T> + std::marker::Unpin,
+where I: futures::stream::Stream<Item=T> + Unpin,
       T: AsRef<[u8]>
 {
     use futures::stream::StreamExt;
@@ -182,12 +447,46 @@ where I: IntoIterator<Item=T>,
     Sha256Hash{hash}
 }
 
+/// Compute a SHA256 hash from a number of fallible slices, stopping at the first error.
+///
+/// # Notes
+/// Nothing past the first `Err` is taken from the iterator.
+pub fn compute_try_slices<I, T, E>(from: I) -> Result<Sha256Hash, E>
+where I: IntoIterator<Item=Result<T, E>>,
+      T: AsRef<[u8]>
+{
+    let mut hasher = Sha256::new();
+    for from in from.into_iter() {
+	hasher.update(from?.as_ref());
+    }
+
+    let mut hash = [0u8; SIZE];
+    bytes::copy_slice(&mut hash, &hasher.finalize());
+    Ok(Sha256Hash{hash})
+}
+
 
 /// Compute the SHA256 hash of the rest of this stream
-pub fn compute_sync<T>(mut from: T) -> io::Result<Sha256Hash>
+#[cfg(feature="std")]
+#[inline] pub fn compute_sync<T>(from: T) -> io::Result<Sha256Hash>
 where T: io::Read
 {
-    let mut buffer = [0u8; super::BUFFER_SIZE];
+    compute_sync_buffered(from, super::BUFFER_SIZE)
+}
+
+/// Compute the SHA256 hash of the rest of this stream, reading `buffer_size` bytes at a time.
+///
+/// # Notes
+/// Buffers larger than `BUFFER_SIZE` are allocated on the heap.
+///
+/// # Panics
+/// If `buffer_size` is 0.
+#[cfg(feature="std")]
+pub fn compute_sync_buffered<T>(mut from: T, buffer_size: usize) -> io::Result<Sha256Hash>
+where T: io::Read
+{
+    assert!(buffer_size > 0, "buffer size cannot be 0");
+    let mut buffer = bytes::Buffer::<{super::BUFFER_SIZE}>::new(buffer_size);
     let mut hasher = Sha256::new();
     let mut read:usize;
     while (read = from.read(&mut buffer[..])?, read!=0).1 {
@@ -199,6 +498,106 @@ where T: io::Read
     Ok(Sha256Hash{hash})
 }
 
+/// Compute the SHA256 hash of the next `limit` bytes of this stream.
+///
+/// Nothing after those bytes is read, so the rest of the stream can still be read from `from`.
+///
+/// # Errors
+/// `io::ErrorKind::UnexpectedEof` if the stream ends before `limit` bytes were read.
+#[cfg(feature="std")]
+pub fn compute_limited_sync<T>(from: &mut T, limit: u64) -> io::Result<Sha256Hash>
+where T: io::Read + ?Sized
+{
+    let mut buffer = [0u8; super::BUFFER_SIZE];
+    let mut hasher = Sha256::new();
+    let mut remaining = limit;
+    while remaining > 0 {
+	let want = usize::try_from(remaining).map_or(buffer.len(), |remaining| remaining.min(buffer.len()));
+	let read = from.read(&mut buffer[..want])?;
+	if read == 0 {
+	    return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "stream ended before the limit"));
+	}
+	hasher.update(&buffer[..read]);
+	remaining -= read as u64;
+    }
+
+    Ok(hasher.into())
+}
+
+/// Compute a single SHA256 hash over every file in a directory and its subdirectories.
+///
+/// The entries are hashed in byte-wise order of their paths relative to `path`, with `/` as the separator, so the result only depends on the names and contents of the entries and not on the order the OS lists them in.
+/// For each entry its relative path is hashed, then the SHA256 hash of a file's contents or the target of a symbolic link.
+///
+/// # Notes
+/// Symbolic links are not followed, whether they point to files or directories. Only the path they point to is hashed, so changing the target of a link changes the result but changing the file it points to does not.
+/// Empty directories do not contribute to the result.
+/// On non-unix platforms, paths that are not valid unicode are hashed lossily.
+///
+/// # Errors
+/// Only regular files, directories and symbolic links are supported. If the tree contains anything else, such as a FIFO, socket or device, an `InvalidInput` error is returned rather than reading from it.
+#[cfg(feature="fs")]
+pub fn compute_dir_sync(path: impl AsRef<Path>) -> io::Result<Sha256Hash>
+{
+    let mut entries = Vec::new();
+    walk_dir(path.as_ref(), Vec::new(), &mut entries)?;
+    entries.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+
+    let mut hasher = Sha256::new();
+    for (relative, full) in entries {
+	hasher.update((relative.len() as u64).to_le_bytes());
+	hasher.update(&relative[..]);
+	let metadata = fs::symlink_metadata(&full)?;
+	if metadata.file_type().is_symlink() {
+	    let target = path_bytes(&fs::read_link(&full)?);
+	    hasher.update(b"l");
+	    hasher.update((target.len() as u64).to_le_bytes());
+	    hasher.update(&target[..]);
+	} else if metadata.file_type().is_file() {
+	    hasher.update(b"f");
+	    hasher.update(compute_sync(fs::File::open(&full)?)?.as_ref());
+	} else {
+	    return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("{} is not a regular file, directory or symbolic link", full.display())));
+	}
+    }
+    Ok(hasher.into())
+}
+
+/// Collect the relative `/` separated paths and full paths of every file and symbolic link under `dir`
+#[cfg(feature="fs")]
+fn walk_dir(dir: &Path, prefix: Vec<u8>, entries: &mut Vec<(Vec<u8>, PathBuf)>) -> io::Result<()>
+{
+    for entry in fs::read_dir(dir)? {
+	let entry = entry?;
+	let mut relative = prefix.clone();
+	if !relative.is_empty() {
+	    relative.push(b'/');
+	}
+	relative.extend_from_slice(&path_bytes(Path::new(&entry.file_name()))[..]);
+
+	// `DirEntry::file_type()` does not follow symbolic links
+	if entry.file_type()?.is_dir() {
+	    walk_dir(&entry.path(), relative, entries)?;
+	} else {
+	    entries.push((relative, entry.path()));
+	}
+    }
+    Ok(())
+}
+
+#[cfg(all(feature="fs", unix))]
+#[inline] fn path_bytes(path: &Path) -> Vec<u8>
+{
+    use std::os::unix::ffi::OsStrExt;
+    path.as_os_str().as_bytes().to_vec()
+}
+
+#[cfg(all(feature="fs", not(unix)))]
+#[inline] fn path_bytes(path: &Path) -> Vec<u8>
+{
+    path.to_string_lossy().replace('\\', "/").into_bytes()
+}
+
 impl AsRef<[u8]> for Sha256Hash
 {
     #[inline] fn as_ref(&self) -> &[u8]
@@ -223,6 +622,74 @@ impl From<[u8; SIZE]> for Sha256Hash
     }
 }
 
+impl Sha256Hash
+{
+    /// Check if this hash matches a hex string, comparing in constant time.
+    ///
+    /// Both upper and lower case hex are accepted. Returns `false` if `expected` is not valid hex for a SHA256 hash.
+    pub fn matches_hex(&self, expected: impl AsRef<str>) -> bool
+    {
+	let mut hash = [0u8; SIZE];
+	bytes::from_hex_into(expected.as_ref(), &mut hash[..]) && bytes::ct_eq(&hash[..], &self.hash[..])
+    }
+
+    /// Check if the rest of this stream hashes to this hash, comparing in constant time.
+    #[cfg(feature="async")]
+    pub async fn verify_stream<T>(&self, from: &mut T) -> io::Result<bool>
+    where T: AsyncRead + Unpin + ?Sized
+    {
+	let hash = compute(from).await?;
+	Ok(bytes::ct_eq(&hash.hash[..], &self.hash[..]))
+    }
+
+    /// Check if the rest of this stream hashes to this hash, comparing in constant time.
+    #[cfg(feature="std")]
+    pub fn verify_stream_sync<T>(&self, from: &mut T) -> io::Result<bool>
+    where T: io::Read + ?Sized
+    {
+	let hash = compute_sync(from)?;
+	Ok(bytes::ct_eq(&hash.hash[..], &self.hash[..]))
+    }
+}
+
+/// Compares in constant time
+impl PartialEq<[u8; SIZE]> for Sha256Hash
+{
+    #[inline] fn eq(&self, other: &[u8; SIZE]) -> bool
+    {
+	bytes::ct_eq(&self.hash[..], &other[..])
+    }
+}
+
+/// Compares in constant time
+impl PartialEq<Sha256Hash> for [u8; SIZE]
+{
+    #[inline] fn eq(&self, other: &Sha256Hash) -> bool
+    {
+	other == self
+    }
+}
+
+/// Compares in constant time. Slices that are not `SIZE` bytes long are never equal.
+impl PartialEq<[u8]> for Sha256Hash
+{
+    #[inline] fn eq(&self, other: &[u8]) -> bool
+    {
+	bytes::ct_eq(&self.hash[..], other)
+    }
+}
+
+impl TryFrom<&[u8]> for Sha256Hash
+{
+    type Error = TryFromSliceError;
+
+    /// Fails if `from` is not exactly `SIZE` bytes long
+    #[inline] fn try_from(from: &[u8]) -> Result<Self, Self::Error>
+    {
+	Ok(Self { hash: <[u8; SIZE]>::try_from(from)? })
+    }
+}
+
 impl From<Sha256Hash> for [u8; SIZE]
 {
     #[inline] fn from(from: Sha256Hash) -> Self
@@ -239,3 +706,289 @@ impl From<Sha256Hash> for password::Password
 	Self::from_bytes(from.hash)
     }
 }
+
+#[cfg(test)]
+mod tests
+{
+    #[test]
+    fn xor_truncate()
+    {
+	let a = super::compute_slice(b"hello");
+	let b = super::compute_slice(b"world");
+
+	assert_eq!(a.xor(&a), super::Sha256Hash::empty());
+	assert_eq!(a.xor(&b).xor(&b), a);
+	assert_eq!(a.xor(&b), b.xor(&a));
+	assert_eq!(a.xor(&super::Sha256Hash::empty()), a);
+
+	let half: [u8; 16] = a.truncate_to();
+	assert_eq!(&half[..], &a.as_ref()[..16]);
+	assert_eq!(a.truncate_to::<{super::SIZE}>(), a.into_bytes());
+	assert_eq!(a.truncate_to::<0>(), [0u8; 0]);
+    }
+
+    #[test]
+    fn hex_format()
+    {
+	let hash = super::compute_slice(b"abc");
+	const ABC: &str = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+
+	assert_eq!(format!("{:x}", hash), ABC);
+	assert_eq!(format!("{:X}", hash), ABC.to_uppercase());
+	assert_eq!(format!("{:#x}", hash), format!("0x{}", ABC));
+	assert_eq!(format!("{}", hash), format!("SHA256 ({})", ABC));
+	assert_eq!(format!("{:x}", super::Sha256Hash::empty()), "0".repeat(64));
+    }
+
+    #[test]
+    fn try_slices()
+    {
+	let chunks = [&b"hello"[..], &b" "[..], &b"world"[..]];
+	assert_eq!(super::compute_try_slices(chunks.iter().map(Ok::<_, ()>)), Ok(super::compute_slices(chunks.iter())));
+	assert_eq!(super::compute_try_slices(std::iter::empty::<Result<&[u8], ()>>()), Ok(super::compute_slice([])));
+
+	let mut taken = 0;
+	let result = super::compute_try_slices(vec![Ok(&b"hello"[..]), Err("bad chunk"), Ok(&b"world"[..])].into_iter().inspect(|_| taken += 1));
+	assert_eq!(result, Err("bad chunk"));
+	assert_eq!(taken, 2);
+    }
+
+    #[test]
+    fn as_array()
+    {
+	fn takes_array(array: &[u8; 32]) -> u8
+	{
+	    array[31]
+	}
+
+	let mut hash = super::compute_slice(b"hello world");
+	assert_eq!(takes_array(hash.as_array()), hash.as_ref()[31]);
+	assert_eq!(&hash.as_array()[..], hash.as_ref());
+
+	hash.as_array_mut()[0] ^= 0xff;
+	assert_ne!(hash, super::compute_slice(b"hello world"));
+	hash.as_array_mut()[0] ^= 0xff;
+	assert_eq!(hash, super::compute_slice(b"hello world"));
+    }
+
+    #[cfg(feature="parallel")]
+    #[test]
+    fn parallel()
+    {
+	let data: Vec<u8> = (0..super::PARALLEL_CHUNK_SIZE * 3 + 17).map(|x| x as u8).collect();
+	let hash = super::compute_slice_parallel(&data[..]);
+
+	assert_eq!(super::compute_slice_parallel(&data[..]), hash);
+	assert_ne!(hash, super::compute_slice(&data[..]));
+
+	let chunks: Vec<_> = data.chunks(super::PARALLEL_CHUNK_SIZE).map(super::compute_slice).collect();
+	assert_eq!(chunks.len(), 4);
+	assert_eq!(super::compute_slices(chunks), hash);
+
+	// Only the chunk size matters, not how the work is split between threads
+	let pool = rayon::ThreadPoolBuilder::new().num_threads(1).build().unwrap();
+	assert_eq!(pool.install(|| super::compute_slice_parallel(&data[..])), hash);
+
+	// A small chunk size gives many chunks to split between the threads
+	let small: Vec<_> = data.chunks(7).map(super::compute_slice).collect();
+	assert_eq!(super::compute_slice_parallel_chunked(&data[..], 7), super::compute_slices(small));
+    }
+
+    #[cfg(feature="std")]
+    #[test]
+    fn compute_into_replaces()
+    {
+	let mut hash = super::Sha256Hash::empty();
+	assert_eq!(hash.compute_into_sync(&b"hello "[..]).unwrap(), 6);
+	assert_eq!(hash, super::compute_slice(b"hello "));
+
+	hash.compute_into_sync(&b"world"[..]).unwrap();
+	assert_eq!(hash, super::compute_slice(b"world"));
+
+	hash.reset();
+	assert_eq!(hash, super::Sha256Hash::empty());
+    }
+
+    #[cfg(feature="std")]
+    #[test]
+    fn digest_accumulates()
+    {
+	let mut digest = super::Sha256Digest::new();
+	assert_eq!(digest.update_with_stream_sync(&b"hello "[..]).unwrap(), 6);
+	digest.update(b"wor");
+	digest.update_with_stream_sync(&b"ld"[..]).unwrap();
+
+	assert_eq!(digest.finalize(), super::compute_slice(b"hello world"));
+	assert_eq!(super::Sha256Digest::new().finalize(), super::compute_slice([]));
+    }
+
+    #[cfg(feature="std")]
+    #[test]
+    fn verify_stream()
+    {
+	let data: Vec<u8> = (0..super::BUFFER_SIZE * 3 + 17).map(|x| x as u8).collect();
+	let hash = super::compute_slice(&data[..]);
+
+	assert!(hash.verify_stream_sync(&mut &data[..]).unwrap());
+	assert!(!hash.verify_stream_sync(&mut &data[1..]).unwrap());
+	assert!(!hash.verify_stream_sync(&mut &b""[..]).unwrap());
+	assert!(super::compute_slice([]).verify_stream_sync(&mut &b""[..]).unwrap());
+    }
+
+    #[cfg(feature="async")]
+    #[tokio::test]
+    async fn verify_stream_async()
+    {
+	let data: Vec<u8> = (0..super::BUFFER_SIZE * 3 + 17).map(|x| x as u8).collect();
+	let hash = super::compute_slice(&data[..]);
+
+	assert!(hash.verify_stream(&mut &data[..]).await.unwrap());
+	assert!(!hash.verify_stream(&mut &data[1..]).await.unwrap());
+    }
+
+    #[test]
+    fn compare()
+    {
+	const ABC: &str = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+	let hash = super::compute_slice(b"abc");
+
+	assert!(hash.matches_hex(ABC));
+	assert!(hash.matches_hex(ABC.to_uppercase()));
+	assert!(!hash.matches_hex(super::compute_slice(b"abd").into_bytes().iter().map(|b| format!("{:02x}", b)).collect::<String>()));
+	assert!(!hash.matches_hex(&ABC[..62]));
+	assert!(!hash.matches_hex(format!("{}00", ABC)));
+	assert!(!hash.matches_hex(ABC.replace('b', "g")));
+
+	let bytes = hash.into_bytes();
+	assert!(hash == bytes);
+	assert!(bytes == hash);
+	assert!(hash == bytes[..]);
+	assert!(hash != bytes[..31]);
+	assert!(super::compute_slice(b"abd") != bytes);
+	assert!(super::compute_slice(b"abd") != bytes[..]);
+    }
+
+    #[test]
+    fn try_from_slice()
+    {
+	use std::convert::TryFrom;
+	let data = [0x5au8; super::SIZE + 1];
+
+	assert_eq!(super::Sha256Hash::try_from(&data[..super::SIZE]).unwrap(), super::Sha256Hash::from_bytes([0x5au8; super::SIZE]));
+	assert!(super::Sha256Hash::try_from(&data[..super::SIZE-1]).is_err());
+	assert!(super::Sha256Hash::try_from(&data[..]).is_err());
+    }
+
+    #[cfg(feature="std")]
+    #[test]
+    fn buffered()
+    {
+	let data = vec![0x5au8; super::BUFFER_SIZE * 20 + 3];
+	let hash = super::compute_sync(&data[..]).unwrap();
+
+	assert_eq!(super::compute_sync_buffered(&data[..], 65536).unwrap(), hash);
+	assert_eq!(super::compute_sync_buffered(&data[..], 7).unwrap(), hash);
+	assert_eq!(hash, super::compute_slice(&data[..]));
+    }
+
+    #[cfg(feature="async")]
+    #[tokio::test]
+    async fn compute_buf()
+    {
+	let data: Vec<u8> = (0..super::BUFFER_SIZE * 3 + 17).map(|x| x as u8).collect();
+	let hash = super::compute(&mut &data[..]).await.unwrap();
+
+	assert_eq!(super::compute_buf(&mut tokio::io::BufReader::new(&data[..])).await.unwrap(), hash);
+	assert_eq!(super::compute_buf(&mut tokio::io::BufReader::with_capacity(7, &data[..])).await.unwrap(), hash);
+	assert_eq!(super::compute_buf(&mut &data[..]).await.unwrap(), hash);
+    }
+
+    #[cfg(feature="std")]
+    #[test]
+    fn compute_limited_sync()
+    {
+	use std::io::Read;
+	let data: Vec<u8> = (0..super::BUFFER_SIZE * 2 + 17).map(|x| x as u8).collect();
+
+	let mut from = &data[..];
+	assert_eq!(super::compute_limited_sync(&mut from, 100).unwrap(), super::compute_slice(&data[..100]));
+	let mut rest = Vec::new();
+	from.read_to_end(&mut rest).unwrap();
+	assert_eq!(&rest[..], &data[100..]);
+
+	let mut from = &data[..];
+	assert_eq!(super::compute_limited_sync(&mut from, (super::BUFFER_SIZE + 3) as u64).unwrap(), super::compute_slice(&data[..super::BUFFER_SIZE + 3]));
+	assert_eq!(from, &data[super::BUFFER_SIZE + 3..]);
+
+	assert_eq!(super::compute_limited_sync(&mut &data[..], 0).unwrap(), super::compute_slice(b""));
+	assert_eq!(super::compute_limited_sync(&mut &data[..], data.len() as u64 + 1).unwrap_err().kind(), std::io::ErrorKind::UnexpectedEof);
+    }
+
+    #[cfg(feature="fs")]
+    #[test]
+    fn compute_dir_sync()
+    {
+	use std::fs;
+	let root = std::env::temp_dir().join(format!("cryptohelpers-compute-dir-{}", std::process::id()));
+	let (a, b) = (root.join("a"), root.join("b"));
+	let _ = fs::remove_dir_all(&root);
+
+	// The same tree, created in different orders
+	fs::create_dir_all(a.join("sub")).unwrap();
+	fs::write(a.join("one"), b"hello").unwrap();
+	fs::write(a.join("sub").join("two"), b"world").unwrap();
+	fs::create_dir_all(b.join("sub")).unwrap();
+	fs::write(b.join("sub").join("two"), b"world").unwrap();
+	fs::write(b.join("one"), b"hello").unwrap();
+
+	let hash = super::compute_dir_sync(&a).unwrap();
+	assert_eq!(super::compute_dir_sync(&a).unwrap(), hash);
+	assert_eq!(super::compute_dir_sync(&b).unwrap(), hash);
+
+	fs::write(b.join("sub").join("two"), b"world!").unwrap();
+	assert_ne!(super::compute_dir_sync(&b).unwrap(), hash);
+	fs::write(b.join("sub").join("two"), b"world").unwrap();
+	fs::rename(b.join("sub").join("two"), b.join("sub").join("three")).unwrap();
+	assert_ne!(super::compute_dir_sync(&b).unwrap(), hash);
+
+	#[cfg(unix)]
+	{
+	    let _socket = std::os::unix::net::UnixListener::bind(a.join("socket")).unwrap();
+	    assert_eq!(super::compute_dir_sync(&a).unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+	}
+
+	fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[cfg(feature="async")]
+    #[tokio::test]
+    async fn compute_limited()
+    {
+	use tokio::io::AsyncReadExt;
+	let data: Vec<u8> = (0..super::BUFFER_SIZE * 2 + 17).map(|x| x as u8).collect();
+
+	let mut from = &data[..];
+	assert_eq!(super::compute_limited(&mut from, 100).await.unwrap(), super::compute_slice(&data[..100]));
+	let mut rest = Vec::new();
+	from.read_to_end(&mut rest).await.unwrap();
+	assert_eq!(&rest[..], &data[100..]);
+
+	assert_eq!(super::compute_limited(&mut &data[..], data.len() as u64 + 1).await.unwrap_err().kind(), std::io::ErrorKind::UnexpectedEof);
+    }
+
+    #[cfg(feature="async")]
+    #[tokio::test]
+    async fn writer_matches_slice()
+    {
+	use tokio::io::AsyncWriteExt;
+	let data = b"some data that is written to the sink in a few chunks";
+
+	let mut writer = super::Sha256Writer::new();
+	for chunk in data.chunks(7) {
+	    writer.write_all(chunk).await.unwrap();
+	}
+	writer.shutdown().await.unwrap();
+
+	assert_eq!(writer.into_hash(), super::compute_slice(&data[..]));
+    }
+}
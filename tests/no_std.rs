@@ -0,0 +1,36 @@
+//! Checks the slice hashing functions are usable from a `no_std` crate.
+//!
+//! Run with `cargo test --no-default-features --features sha256,checksum` to also build the library itself without `std`.
+#![no_std]
+
+#[cfg(feature="sha256")]
+#[test]
+fn sha256_slices()
+{
+    use cryptohelpers::sha256;
+    const ABC: [u8; sha256::SIZE] = [
+	0xba, 0x78, 0x16, 0xbf, 0x8f, 0x01, 0xcf, 0xea, 0x41, 0x41, 0x40, 0xde, 0x5d, 0xae, 0x22, 0x23,
+	0xb0, 0x03, 0x61, 0xa3, 0x96, 0x17, 0x7a, 0x9c, 0xb4, 0x10, 0xff, 0x61, 0xf2, 0x00, 0x15, 0xad,
+    ];
+
+    assert_eq!(sha256::compute_slice(b"abc").into_bytes(), ABC);
+    assert_eq!(sha256::compute_slices([&b"a"[..], &b"bc"[..]]).into_bytes(), ABC);
+    assert_eq!(sha256::compute_slice_iter(b"abc".chunks(1)).into_bytes(), ABC);
+}
+
+#[cfg(feature="checksum")]
+#[test]
+fn crc_slices()
+{
+    use cryptohelpers::crc;
+
+    assert_eq!(crc::compute_slice(b"123456789"), 0x995dc9bbdf1939fa);
+    assert_eq!(crc::compute_slice_with(b"123456789", crc::Polynomial::Iso), 0xb90956c775a41001);
+
+    let mut digest = crc::Crc64::new();
+    digest.update(b"1234");
+    digest.update(b"56789");
+    assert_eq!(digest.finalize(), 0x995dc9bbdf1939fa);
+    assert_eq!(crc::compute_slices([&b"1234"[..], &b"56789"[..]]), 0x995dc9bbdf1939fa);
+    assert_eq!(crc::combine(crc::compute_slice(b"12345"), crc::compute_slice(b"6789"), 4), 0x995dc9bbdf1939fa);
+}